@@ -0,0 +1,32 @@
+//! Compares skipping a large nested array through `IgnoredAny` with
+//! materializing it as a `Value`, run with `cargo bench --bench ignored_any`.
+
+use std::{hint::black_box, time::Instant};
+
+use deseresp::{from_slice, Value};
+use serde::de::IgnoredAny;
+
+const ITERATIONS: usize = 1_000;
+
+fn main() {
+    let mut input = b"*1000\r\n".to_vec();
+    for i in 0..1000 {
+        input.extend_from_slice(b"*3\r\n$5\r\nfield\r\n%1\r\n+k\r\n,1.5\r\n");
+        input.extend_from_slice(format!(":{}\r\n", i).as_bytes());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(from_slice::<_, IgnoredAny>(black_box(&input[..])).unwrap());
+    }
+    let skip = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(from_slice::<_, Value>(black_box(&input[..])).unwrap());
+    }
+    let value = start.elapsed();
+
+    println!("IgnoredAny: {:?}/iter", skip / ITERATIONS as u32);
+    println!("Value:      {:?}/iter", value / ITERATIONS as u32);
+}
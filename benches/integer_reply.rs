@@ -0,0 +1,28 @@
+//! Compares `Deserializer::read_integer_reply` with `from_slice::<i64>`,
+//! run with `cargo bench --bench integer_reply`.
+
+use std::{hint::black_box, time::Instant};
+
+use deseresp::{from_slice, Deserializer};
+
+const ITERATIONS: usize = 1_000_000;
+
+fn main() {
+    let input = b":-1234567890\r\n";
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let mut d = Deserializer::from_slice(black_box(&input[..]));
+        black_box(d.read_integer_reply().unwrap());
+    }
+    let fast = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(from_slice::<_, i64>(black_box(&input[..])).unwrap());
+    }
+    let serde = start.elapsed();
+
+    println!("read_integer_reply: {:?}/iter", fast / ITERATIONS as u32);
+    println!("from_slice::<i64>:  {:?}/iter", serde / ITERATIONS as u32);
+}
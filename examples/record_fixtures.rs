@@ -0,0 +1,115 @@
+//! Records the fixtures of `tests/replies.rs` from a live redis-server
+//!
+//! ```sh
+//! redis-server --port 6399 --save '' &
+//! cargo run --example record_fixtures -- 127.0.0.1:6399
+//! ```
+//!
+//! Each fixture is the bytes read from the socket after `HELLO 3`, frame
+//! by frame, untouched. It writes `user:1` and `fixture:*` keys and
+//! publishes to `news`, so use a throwaway server. The server version is
+//! printed to be noted in the doc of `tests/replies.rs`.
+//!
+//! `attribute.resp` isn't recorded: no command replies with the
+//! spec's `key-popularity` attribute, it stays as written after the spec.
+
+use std::{error::Error, fs, io::Write, net::TcpStream, path::Path};
+
+use deseresp::{types::RawFrame, Command, FrameReader};
+use serde::Deserialize;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Deserialize)]
+struct Hello {
+    version: String,
+}
+
+struct Conn {
+    stream: TcpStream,
+    frames: FrameReader<TcpStream>,
+}
+
+impl Conn {
+    /// Connects and switches the connection to RESP3
+    fn connect(addr: &str) -> Result<(Self, Vec<u8>)> {
+        let stream = TcpStream::connect(addr)?;
+        let frames = FrameReader::new(stream.try_clone()?);
+        let mut conn = Conn { stream, frames };
+        let hello = conn.call("HELLO 3")?;
+
+        Ok((conn, hello))
+    }
+
+    /// Sends a command given as whitespace separated arguments
+    fn send(&mut self, command: &str) -> Result<()> {
+        let command: Command = command.split_ascii_whitespace().collect();
+        self.stream.write_all(&command.encode())?;
+
+        Ok(())
+    }
+
+    /// Reads the next frame, reply or push, as received
+    fn frame(&mut self) -> Result<Vec<u8>> {
+        let frame: RawFrame = self.frames.read_frame()?;
+
+        Ok(frame.0.to_vec())
+    }
+
+    fn call(&mut self, command: &str) -> Result<Vec<u8>> {
+        self.send(command)?;
+        self.frame()
+    }
+}
+
+fn main() -> Result<()> {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:6379".into());
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let record = |name: &str, frames: &[Vec<u8>]| {
+        println!("recorded {}", name);
+        fs::write(dir.join(name), frames.concat())
+    };
+
+    let (mut a, hello) = Conn::connect(&addr)?;
+    let (mut b, _) = Conn::connect(&addr)?;
+    let (mut c, _) = Conn::connect(&addr)?;
+    let info: Hello = deseresp::from_slice(&hello)?;
+    println!("recording from redis {}", info.version);
+    record("hello.resp", &[hello])?;
+
+    a.call("DEL user:1 fixture:get fixture:repo fixture:stream fixture:list")?;
+
+    a.call("SET fixture:get hello")?;
+    record("get.resp", &[a.call("GET fixture:get")?])?;
+    record("get_nil.resp", &[a.call("GET fixture:missing")?])?;
+
+    a.call("HSET fixture:repo name deseresp stars 42 lang rust")?;
+    record("hgetall.resp", &[a.call("HGETALL fixture:repo")?])?;
+
+    a.call("XADD fixture:stream 1526985054069-0 temperature 36 humidity 95")?;
+    a.call("XADD fixture:stream 1526985054079-0 temperature 37 humidity 94")?;
+    record("xrange.resp", &[a.call("XRANGE fixture:stream - +")?])?;
+
+    record("client_info.resp", &[a.call("CLIENT INFO")?])?;
+
+    a.call("LPUSH fixture:list a")?;
+    record("error_wrongtype.resp", &[a.call("GET fixture:list")?])?;
+    record("error_unknown_command.resp", &[a.call("FOO bar")?])?;
+
+    // the invalidation of `user:1` arrives before the reply to the next GET
+    b.call("SET user:1 alice")?;
+    let ok = a.call("CLIENT TRACKING ON")?;
+    a.call("GET user:1")?;
+    b.call("SET user:1 alice")?;
+    a.send("GET user:1")?;
+    let (push, reply) = (a.frame()?, a.frame()?);
+    record("tracking.resp", &[ok, push, reply])?;
+
+    let subscribe = c.call("SUBSCRIBE news")?;
+    b.call("PUBLISH news hello")?;
+    record("subscribe.resp", &[subscribe, c.frame()?])?;
+
+    Ok(())
+}
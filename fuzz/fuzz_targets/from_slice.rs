@@ -0,0 +1,52 @@
+//! Feeds arbitrary bytes to the deserializer, any input must be accepted
+//! or rejected with an error, never panic.
+//!
+//! Run with `cargo +nightly fuzz run from_slice`.
+#![no_main]
+
+use std::collections::HashMap;
+
+use deseresp::{
+    types::{owned::SimpleError, Push, VerbatimString, WithAttribute, WithRawAttribute},
+    Deserializer, Value,
+};
+use libfuzzer_sys::fuzz_target;
+use serde::{de::IgnoredAny, Deserialize};
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Reply<'a> {
+    name: &'a str,
+    count: Option<u64>,
+    values: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+enum Message {
+    Message(String, String),
+    Subscribe(String, u64),
+}
+
+fn check<'a, T: Deserialize<'a>>(data: &'a [u8]) {
+    let _ = deseresp::from_slice_checked::<_, T>(data);
+}
+
+fuzz_target!(|data: &[u8]| {
+    check::<Value>(data);
+    check::<IgnoredAny>(data);
+    check::<Reply>(data);
+    check::<Vec<Message>>(data);
+    check::<HashMap<String, i64>>(data);
+    check::<(i128, String, bool)>(data);
+    check::<SimpleError>(data);
+    check::<VerbatimString>(data);
+    check::<Push<Vec<String>>>(data);
+    check::<WithAttribute<Value, Value>>(data);
+    check::<WithRawAttribute<Value>>(data);
+    let _ = deseresp::tokenize(data);
+
+    // the stream readers share the same parser
+    let mut d = Deserializer::from_read(data).with_max_depth(128);
+    let _ = Value::deserialize(&mut d);
+});
@@ -0,0 +1,104 @@
+//! Adapters reading a simple/blob string into a stack-allocated
+//! `heapless::String<N>`, to be used with `#[serde(with = "...")]`.
+//!
+//! A string longer than `N` bytes fails with [`Error::LengthLimitExceeded`](crate::Error).
+//!
+//! ```rust
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Reply {
+//!     #[serde(with = "deseresp::bounded")]
+//!     name: heapless::String<8>,
+//! }
+//!
+//! let reply: Reply = deseresp::from_slice(b"%1\r\n+name\r\n+redis\r\n").unwrap();
+//! assert_eq!(reply.name, "redis");
+//! ```
+
+use serde::{de, Deserializer, Serializer};
+
+use crate::types::BOUNDED_STRING_TOKEN;
+
+struct BoundedStringVisitor<const N: usize>;
+
+impl<'de, const N: usize> de::Visitor<'de> for BoundedStringVisitor<N> {
+    type Value = heapless::String<N>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting str of at most {} bytes", N)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let mut s = heapless::String::new();
+        s.push_str(v)
+            .map_err(|_e| de::Error::invalid_length(v.len(), &self))?;
+        Ok(s)
+    }
+}
+
+/// Serialize `heapless::String<N>` as a simple string
+pub fn serialize<S, const N: usize>(
+    v: &heapless::String<N>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(v)
+}
+
+/// Deserialize `heapless::String<N>` from a simple/blob string
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<heapless::String<N>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // the length travels as the tuple struct length to let [`crate::Deserializer`]
+    // check it before visiting
+    deserializer.deserialize_tuple_struct(BOUNDED_STRING_TOKEN, N, BoundedStringVisitor::<N>)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        test_utils::{test_deserialize, test_deserialize_result},
+        to_vec, Error,
+    };
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Bounded(#[serde(with = "super")] heapless::String<8>);
+
+    #[test]
+    fn test_deserialize_bounded() {
+        test_deserialize(b"+fits\r\n", |value: Bounded| {
+            assert_eq!(value.0, "fits");
+        });
+        test_deserialize(b"$8\r\nexactly8\r\n", |value: Bounded| {
+            assert_eq!(value.0, "exactly8");
+        });
+    }
+
+    #[test]
+    fn test_deserialize_bounded_overflow() {
+        test_deserialize_result(b"+does not fit\r\n", |value: Result<Bounded, Error>| {
+            assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+        });
+        test_deserialize_result(b"$9\r\nnine char\r\n", |value: Result<Bounded, Error>| {
+            assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+        });
+        test_deserialize_result(b":1\r\n", |value: Result<Bounded, Error>| {
+            assert!(value.is_err());
+        });
+    }
+
+    #[test]
+    fn test_serialize_bounded() {
+        let value = Bounded(heapless::String::try_from("abc").unwrap());
+        assert_eq!(to_vec(&value).unwrap(), b"+abc\r\n");
+    }
+}
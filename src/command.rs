@@ -0,0 +1,84 @@
+/// A command to send to the server, encoded as RESP array of blob strings
+/// which is the only request form Redis accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    args: Vec<Vec<u8>>,
+}
+
+impl Command {
+    /// Starts a command with its name, e.g. `GET`
+    pub fn new(name: impl AsRef<[u8]>) -> Self {
+        Command {
+            args: vec![name.as_ref().to_vec()],
+        }
+    }
+
+    /// Appends an argument
+    pub fn arg(mut self, arg: impl AsRef<[u8]>) -> Self {
+        self.args.push(arg.as_ref().to_vec());
+        self
+    }
+
+    /// Appends the encoded command to `buf`
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(format!("*{}\r\n", self.args.len()).as_bytes());
+        for arg in &self.args {
+            buf.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+            buf.extend_from_slice(arg);
+            buf.extend_from_slice(b"\r\n");
+        }
+    }
+
+    /// Encodes the command to a new buffer
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode_into(&mut buf);
+        buf
+    }
+}
+
+impl<A: AsRef<[u8]>> FromIterator<A> for Command {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Command {
+            args: iter.into_iter().map(|a| a.as_ref().to_vec()).collect(),
+        }
+    }
+}
+
+/// Encodes commands back to back into a single buffer, to be sent with
+/// one write and have their replies read in order (pipelining)
+pub fn encode_pipeline<I>(commands: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = Command>,
+{
+    let mut buf = Vec::new();
+    for command in commands {
+        command.encode_into(&mut buf);
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_command() {
+        let cmd = Command::new("SET").arg("k").arg(b"\x00\r\n");
+        assert_eq!(
+            cmd.encode(),
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$3\r\n\x00\r\n\r\n"
+        );
+
+        let cmd: Command = ["GET", "k"].into_iter().collect();
+        assert_eq!(cmd, Command::new("GET").arg("k"));
+    }
+
+    #[test]
+    fn test_encode_pipeline() {
+        let buf = encode_pipeline([Command::new("PING"), ["GET", "k"].into_iter().collect()]);
+        assert_eq!(buf, b"*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET\r\n$1\r\nk\r\n");
+
+        assert!(encode_pipeline([]).is_empty());
+    }
+}
@@ -1,16 +1,23 @@
 use std::{
     io::{self, Read},
+    ops::Deref,
     str,
 };
 
 use num::{CheckedAdd, CheckedMul};
 use serde::{
-    de::{DeserializeOwned, Unexpected},
+    de::{value::BorrowedStrDeserializer, DeserializeOwned, IntoDeserializer, Unexpected},
     Deserialize,
 };
 
 use crate::{
-    types::{AttributeSkip, PushSkip},
+    error::str_from_utf8,
+    markers,
+    types::{
+        borrowed::{BlobError, SimpleError},
+        AnySkip,
+    },
+    value::{Frame, Kind, RespType, Value},
     Error, Result,
 };
 
@@ -20,6 +27,17 @@ pub enum Reference<'b, 'c, T: ?Sized + 'static> {
     Copied(&'c T),
 }
 
+impl<'b, 'c, T: ?Sized + 'static> Deref for Reference<'b, 'c, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match *self {
+            Reference::Borrowed(b) => b,
+            Reference::Copied(c) => c,
+        }
+    }
+}
+
 /// Reader provides functionalities in reading RESP bytes
 /// for different readable sources.
 pub trait Reader<'de> {
@@ -105,6 +123,9 @@ pub trait Reader<'de> {
             inf = true;
         }
         if inf {
+            if !matches!(self.peek_u8()?, Some(b'\r' | b'\n') | None) {
+                return Err(Error::parse());
+            }
             if negative {
                 return Ok(f64::NEG_INFINITY);
             } else {
@@ -122,10 +143,20 @@ pub trait Reader<'de> {
                 _ => break,
             }
         }
-        let str = str::from_utf8(&buf[..]).map_err(|e| Error::utf8(e.valid_up_to()))?;
+        if buf == b"nan" && !negative {
+            return Ok(f64::NAN);
+        }
+        if !is_double_literal(&buf) {
+            return Err(Error::parse());
+        }
+        let str = str_from_utf8(&buf[..])?;
         let result = str.parse::<f64>().map_err(|_e| Error::parse())?;
 
-        Ok(result)
+        if negative {
+            Ok(-result)
+        } else {
+            Ok(result)
+        }
     }
 
     /// Consumes a boolean from this point
@@ -141,6 +172,7 @@ pub trait Reader<'de> {
                 self.read_crlf()?;
                 Ok(false)
             }
+            None => Err(Error::eof()),
             _ => Err(Error::expected_value("bool")),
         }
     }
@@ -148,10 +180,35 @@ pub trait Reader<'de> {
     /// Consumes a provided bytes from this point
     fn read_ident(&mut self, ident: &[u8]) -> Result<()>;
 
+    /// Number of bytes left to read, if known without reading them
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+
+    /// Bytes left to read, for readers borrowing their whole input
+    fn remaining_slice(&self) -> Option<&'de [u8]> {
+        None
+    }
+
     /// Consumes <cr><lf> from this point
     fn read_crlf(&mut self) -> Result<()> {
         self.read_ident(b"\r\n")
     }
+
+    /// Accepts a bare <lf> wherever <cr><lf> is expected
+    fn set_lenient_line_endings(&mut self, lenient: bool);
+
+    /// Whether a bare <lf> is accepted, see [`Reader::set_lenient_line_endings`]
+    fn lenient_line_endings(&self) -> bool;
+
+    /// Starts keeping a copy of the bytes consumed from this point,
+    /// returning the mark to pass to [`Reader::end_capture`]. Captures
+    /// may nest.
+    fn start_capture(&mut self) -> usize;
+
+    /// Ends the capture started at `mark`, returning the bytes consumed
+    /// since then exactly as they were read
+    fn end_capture(&mut self, mark: usize) -> Vec<u8>;
 }
 
 /// Reader that wrap an underlying Read
@@ -159,40 +216,92 @@ pub struct ReadReader<R: Read> {
     r: io::Bytes<R>,
     ch: Option<u8>,
     buf: Vec<u8>,
+    max_line_len: usize,
+    lenient_line_endings: bool,
+    // bytes consumed while a capture is open
+    record: Option<Vec<u8>>,
+    captures: usize,
 }
 
-fn peek_u8<R: Read>(r: &mut io::Bytes<R>, ch: &mut Option<u8>) -> Result<Option<u8>> {
-    match ch {
-        Some(next) => Ok(Some(*next)),
-        None => read_u8(r, ch),
+/// Checks an unsigned RESP double: `<integral>[.<fractional>][<e|E>[sign]<exponent>]`
+fn is_double_literal(bytes: &[u8]) -> bool {
+    fn digits(bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|ch| ch.is_ascii_digit()).count()
+    }
+
+    let integral = digits(bytes);
+    if integral == 0 {
+        return false;
+    }
+    let mut rest = &bytes[integral..];
+    if let Some(after_dot) = rest.strip_prefix(b".") {
+        let fractional = digits(after_dot);
+        if fractional == 0 {
+            return false;
+        }
+        rest = &after_dot[fractional..];
+    }
+    if let Some(after_e) = rest.strip_prefix(b"e").or_else(|| rest.strip_prefix(b"E")) {
+        let after_sign = after_e
+            .strip_prefix(b"-")
+            .or_else(|| after_e.strip_prefix(b"+"))
+            .unwrap_or(after_e);
+        let exponent = digits(after_sign);
+        if exponent == 0 {
+            return false;
+        }
+        rest = &after_sign[exponent..];
     }
-}
 
-fn read_u8<R: Read>(r: &mut io::Bytes<R>, ch: &mut Option<u8>) -> Result<Option<u8>> {
-    r.next().transpose().map_err(Error::io).map(|next| {
-        *ch = next;
-        next
-    })
+    rest.is_empty()
 }
 
-fn read_reader_ident<R: Read>(
-    r: &mut io::Bytes<R>,
-    ch: &mut Option<u8>,
-    ident: &[u8],
-) -> Result<()> {
-    for expected in ident {
-        match peek_u8(r, ch)? {
-            None => return Err(Error::eof()),
-            Some(next) => {
-                if next != *expected {
-                    return Err(Error::expected_value("ident"));
+impl<R: Read> ReadReader<R> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        match self.ch {
+            Some(next) => Ok(Some(next)),
+            None => self.bump(),
+        }
+    }
+
+    /// Moves to the next byte of the underlying Read, the byte it replaces
+    /// is consumed
+    fn bump(&mut self) -> Result<Option<u8>> {
+        let next = self.r.next().transpose().map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock => Error::incomplete(),
+            _ => Error::io(e),
+        })?;
+        if let (Some(record), Some(consumed)) = (&mut self.record, self.ch) {
+            record.push(consumed);
+        }
+        self.ch = next;
+
+        Ok(next)
+    }
+
+    fn ident(&mut self, ident: &[u8]) -> Result<()> {
+        for expected in ident {
+            match self.peek()? {
+                None => return Err(Error::eof()),
+                Some(next) => {
+                    if next != *expected {
+                        return Err(Error::expected_value("ident"));
+                    }
+                    self.bump()?;
                 }
-                read_u8(r, ch)?;
             }
         }
+
+        Ok(())
     }
 
-    Ok(())
+    fn crlf(&mut self) -> Result<()> {
+        if self.lenient_line_endings && self.peek()? == Some(b'\n') {
+            self.bump()?;
+            return Ok(());
+        }
+        self.ident(b"\r\n")
+    }
 }
 
 impl<'de, R: Read> Reader<'de> for ReadReader<R> {
@@ -203,13 +312,13 @@ impl<'de, R: Read> Reader<'de> for ReadReader<R> {
     ) -> Result<Reference<'de, 'a, [u8]>> {
         self.buf.clear();
         for _count in 0..len {
-            let ch = peek_u8(&mut self.r, &mut self.ch)?.ok_or_else(Error::eof)?;
+            let ch = self.peek()?.ok_or_else(Error::eof)?;
             self.buf.push(ch);
-            read_u8(&mut self.r, &mut self.ch)?;
+            self.bump()?;
         }
 
         if consume_crlf {
-            read_reader_ident(&mut self.r, &mut self.ch, b"\r\n")?;
+            self.crlf()?;
         }
 
         Ok(Reference::Copied(&self.buf[..]))
@@ -225,39 +334,72 @@ impl<'de, R: Read> Reader<'de> for ReadReader<R> {
     {
         self.buf.clear();
         loop {
-            let ch = peek_u8(&mut self.r, &mut self.ch)?.ok_or_else(Error::eof)?;
+            let ch = self.peek()?.ok_or_else(Error::eof)?;
             if until_fn(ch) {
                 break;
             }
+            if self.buf.len() >= self.max_line_len {
+                return Err(Error::length_limit_exceeded());
+            }
             self.buf.push(ch);
-            read_u8(&mut self.r, &mut self.ch)?;
+            self.bump()?;
         }
 
         if consume_crlf {
-            read_reader_ident(&mut self.r, &mut self.ch, b"\r\n")?;
+            self.crlf()?;
         }
 
         Ok(Reference::Copied(&self.buf[..]))
     }
 
     fn peek_u8(&mut self) -> Result<Option<u8>> {
-        peek_u8(&mut self.r, &mut self.ch)
+        self.peek()
     }
 
     fn read_u8(&mut self) -> Result<Option<u8>> {
-        read_u8(&mut self.r, &mut self.ch)
+        self.bump()
     }
 
     fn read_ident(&mut self, ident: &[u8]) -> Result<()> {
-        read_reader_ident(&mut self.r, &mut self.ch, ident)
+        self.ident(ident)
+    }
+
+    fn read_crlf(&mut self) -> Result<()> {
+        self.crlf()
+    }
+
+    fn set_lenient_line_endings(&mut self, lenient: bool) {
+        self.lenient_line_endings = lenient;
+    }
+
+    fn lenient_line_endings(&self) -> bool {
+        self.lenient_line_endings
+    }
+
+    fn start_capture(&mut self) -> usize {
+        self.captures += 1;
+        self.record.get_or_insert_with(Vec::new).len()
+    }
+
+    fn end_capture(&mut self, mark: usize) -> Vec<u8> {
+        self.captures -= 1;
+        let record = self.record.as_deref().unwrap_or_default();
+        let captured = record[mark..].to_vec();
+        if self.captures == 0 {
+            self.record = None;
+        }
+
+        captured
     }
 }
 
 /// Reader that wrap an underlying slice of bytes
 pub struct RefReader<'de, R: AsRef<[u8]> + ?Sized> {
     slice: &'de R,
-    src: &'de [u8],
     buf: &'de [u8],
+    // bytes of the slice before buf
+    consumed: usize,
+    lenient_line_endings: bool,
 }
 
 impl<'de, R: AsRef<[u8]> + ?Sized> RefReader<'de, R> {
@@ -266,24 +408,43 @@ impl<'de, R: AsRef<[u8]> + ?Sized> RefReader<'de, R> {
         let buf = slice.as_ref();
         RefReader {
             slice,
-            src: buf,
             buf,
+            consumed: 0,
+            lenient_line_endings: false,
         }
     }
 
     /// Get number of bytes consumed from this reader
     pub fn consumed_bytes(&self) -> usize {
-        // SAFETY: buf is advanced from src
-        unsafe { self.buf.as_ptr().offset_from(self.src.as_ptr()) as usize }
+        self.consumed
     }
-}
 
-fn read_slice_ident<'de>(buf: &mut &'de [u8], ident: &[u8]) -> Result<()> {
-    if buf.starts_with(ident) {
-        *buf = &buf[ident.len()..];
-        Ok(())
-    } else {
-        Err(Error::expected_value("ident"))
+    /// Moves past the next `len` bytes, all advances go through here to
+    /// keep the consumed count in sync
+    fn advance(&mut self, len: usize) -> &'de [u8] {
+        let (a, b) = self.buf.split_at(len);
+        self.buf = b;
+        self.consumed += len;
+        a
+    }
+
+    fn read_slice_ident(&mut self, ident: &[u8]) -> Result<()> {
+        if self.buf.starts_with(ident) {
+            self.advance(ident.len());
+            Ok(())
+        } else if ident.starts_with(self.buf) {
+            Err(Error::eof())
+        } else {
+            Err(Error::expected_value("ident"))
+        }
+    }
+
+    fn read_slice_crlf(&mut self) -> Result<()> {
+        if self.lenient_line_endings && self.buf.starts_with(b"\n") {
+            self.advance(1);
+            return Ok(());
+        }
+        self.read_slice_ident(b"\r\n")
     }
 }
 
@@ -297,10 +458,9 @@ impl<'de, R: AsRef<[u8]> + ?Sized> Reader<'de> for RefReader<'de, R> {
             return Err(Error::eof());
         }
 
-        let (a, b) = self.buf.split_at(len);
-        self.buf = b;
+        let a = self.advance(len);
         if consume_crlf {
-            read_slice_ident(&mut self.buf, b"\r\n")?;
+            self.read_slice_crlf()?;
         }
 
         Ok(Reference::Borrowed(a))
@@ -320,10 +480,9 @@ impl<'de, R: AsRef<[u8]> + ?Sized> Reader<'de> for RefReader<'de, R> {
             .position(|ch| until_fn(*ch))
             .ok_or_else(Error::eof)?;
 
-        let (a, b) = self.buf.split_at(len);
-        self.buf = b;
+        let a = self.advance(len);
         if consume_crlf {
-            read_slice_ident(&mut self.buf, b"\r\n")?;
+            self.read_slice_crlf()?;
         }
 
         Ok(Reference::Borrowed(a))
@@ -337,33 +496,249 @@ impl<'de, R: AsRef<[u8]> + ?Sized> Reader<'de> for RefReader<'de, R> {
         if self.buf.is_empty() {
             return Ok(None);
         }
-        let ch = self.buf[0];
-        self.buf = &self.buf[1..];
+        let ch = self.advance(1)[0];
 
         Ok(Some(ch))
     }
 
     fn read_ident(&mut self, ident: &[u8]) -> Result<()> {
-        read_slice_ident(&mut self.buf, ident)
+        self.read_slice_ident(ident)
+    }
+
+    fn read_crlf(&mut self) -> Result<()> {
+        self.read_slice_crlf()
+    }
+
+    fn set_lenient_line_endings(&mut self, lenient: bool) {
+        self.lenient_line_endings = lenient;
+    }
+
+    fn lenient_line_endings(&self) -> bool {
+        self.lenient_line_endings
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.buf.len())
+    }
+
+    fn remaining_slice(&self) -> Option<&'de [u8]> {
+        Some(self.buf)
+    }
+
+    fn start_capture(&mut self) -> usize {
+        self.consumed
+    }
+
+    fn end_capture(&mut self, mark: usize) -> Vec<u8> {
+        self.slice.as_ref()[mark..self.consumed].to_vec()
     }
 }
 
 /// A RESP Deserializer
+///
+/// `Vec<u8>` is a sequence to serde, it's only read from an array of
+/// integers (`*3\r\n:1\r\n:2\r\n:3\r\n`). To read the raw bytes of a
+/// blob string (`$3\r\n\x01\x02\x03\r\n`) use a type deserializing
+/// through `deserialize_bytes`, like `serde_bytes::ByteBuf`, which accepts
+/// both.
+///
+/// Strings and blobs are borrowed from the input when deserializing from a
+/// slice, so `&str` and `&[u8]` targets (e.g. `HashMap<&str, i64>` keys)
+/// are zero-copy. Readers over [`Read`] copy each value into a buffer,
+/// borrowed targets error there, `String` or `Cow<str>` work with both. A
+/// `Cow<'de, str>` field marked `#[serde(borrow)]` is `Cow::Borrowed` from
+/// a slice and `Cow::Owned` from a [`Read`].
+///
+/// Map entries are visited in wire order and duplicate keys are passed
+/// through as is: map collections (`HashMap`, `BTreeMap`) keep the last
+/// value, [`Value::Map`] keeps every entry, and derived structs reject
+/// a duplicate field.
 pub struct Deserializer<R> {
     reader: R,
-    skip_attribute: bool,
-    skip_push: bool,
+    options: Options,
+    depth: usize,
+    // skipped attributes, when collected
+    attributes: Option<Vec<Vec<u8>>>,
+    // chunks of a streamed string, joined
+    scratch: Vec<u8>,
+}
+
+/// Options of a [`Deserializer`], kept together so a Deserializer over
+/// another reader can be given the same ones
+#[derive(Clone, Copy)]
+pub(crate) struct Options {
+    pub(crate) skip_attribute: bool,
+    pub(crate) skip_push: bool,
+    pub(crate) assume_ordered_map: bool,
+    pub(crate) max_length: usize,
+    pub(crate) max_depth: usize,
+    pub(crate) resp2_compat: bool,
+    pub(crate) coerce_integral_double: bool,
+    pub(crate) coerce_string_number: bool,
+    pub(crate) deny_unread_elements: bool,
+    pub(crate) deny_lossy_integer_double: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            skip_attribute: true,
+            skip_push: true,
+            assume_ordered_map: false,
+            max_length: usize::MAX,
+            max_depth: usize::MAX,
+            resp2_compat: false,
+            coerce_integral_double: false,
+            coerce_string_number: false,
+            deny_unread_elements: false,
+            deny_lossy_integer_double: false,
+        }
+    }
+}
+
+impl<R> Deserializer<R> {
+    fn new(reader: R) -> Self {
+        Deserializer {
+            reader,
+            options: Options::default(),
+            depth: 0,
+            attributes: None,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Replaces all options at once, see [`Options`]
+    pub(crate) fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Assume map keys arrive in the declared field order when deserializing
+    /// a struct. Keys matching the expected field are assigned positionally
+    /// without string matching, keys out of order fall back to matching by name.
+    pub fn assume_ordered_map(mut self, assume: bool) -> Self {
+        self.options.assume_ordered_map = assume;
+        self
+    }
+
+    /// Limits the accepted length prefix of blobs, arrays and maps,
+    /// exceeding lengths are rejected before any allocation.
+    /// Use this when parsing untrusted input.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.options.max_length = max_length;
+        self
+    }
+
+    /// Limits the nesting depth of aggregates (array, set, push, map, attribute),
+    /// deeper input is rejected instead of overflowing the stack.
+    /// Use this when parsing untrusted input.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    /// Accepts RESP2 encodings where RESP3 has a dedicated type:
+    /// `:1`/`:0` integers are accepted as booleans.
+    pub fn resp2_compat(mut self, compat: bool) -> Self {
+        self.options.resp2_compat = compat;
+        self
+    }
+
+    /// Accepts doubles with no fractional part (`,10\r\n`) when an integer
+    /// is expected, doubles with a fractional part or out of range still error
+    pub fn coerce_integral_double(mut self, coerce: bool) -> Self {
+        self.options.coerce_integral_double = coerce;
+        self
+    }
+
+    /// Parses simple and blob strings (`$4\r\n1.23\r\n`) when a number is
+    /// expected, as Redis returns many numbers as strings (`ZSCORE`,
+    /// `HGET`, ...). Strings that aren't a number error.
+    pub fn coerce_string_number(mut self, coerce: bool) -> Self {
+        self.options.coerce_string_number = coerce;
+        self
+    }
+
+    /// Errors when the target type doesn't read all elements of an aggregate,
+    /// e.g. a 3 elements array into a 2-tuple. By default, unread elements
+    /// are skipped so the reader still ends at the frame boundary.
+    pub fn deny_unread_elements(mut self, deny: bool) -> Self {
+        self.options.deny_unread_elements = deny;
+        self
+    }
+
+    /// Skips attributes (`|`) preceding values, enabled by default.
+    /// When disabled, an attribute is only accepted where the target type
+    /// expects one (a map, or [`WithAttribute`](crate::types::WithAttribute)),
+    /// elsewhere it is an error. [`WithAttribute`](crate::types::WithAttribute)
+    /// turns skipping off while reading its attribute and value, and
+    /// restores this setting after.
+    pub fn set_skip_attribute(&mut self, skip: bool) {
+        self.options.skip_attribute = skip;
+    }
+
+    /// Whether attributes preceding values are skipped,
+    /// see [`Deserializer::set_skip_attribute`]
+    pub fn skip_attribute(&self) -> bool {
+        self.options.skip_attribute
+    }
+
+    /// Errors when an integer (`:`) read as a double is above 2^53 in
+    /// magnitude, where it can't be represented exactly. By default the
+    /// integer is rounded to the nearest double.
+    pub fn deny_lossy_integer_double(mut self, deny: bool) -> Self {
+        self.options.deny_lossy_integer_double = deny;
+        self
+    }
+
+    /// Records the attributes skipped before values, retrieved with
+    /// [`Deserializer::take_attributes`], so out-of-band metadata such as
+    /// `key-popularity` can be inspected without changing the target type.
+    /// Attributes read by [`WithAttribute`](crate::types::WithAttribute)
+    /// are not recorded.
+    pub fn collect_attributes(mut self, collect: bool) -> Self {
+        self.attributes = collect.then(Vec::new);
+        self
+    }
+
+    /// Takes the attributes recorded since the last call, each one the
+    /// attribute frame (`|<len>\r\n...`) exactly as received, see
+    /// [`Deserializer::collect_attributes`]
+    pub fn take_attributes(&mut self) -> Vec<Vec<u8>> {
+        self.attributes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn nested<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.depth >= self.options.max_depth {
+            return Err(Error::depth_limit_exceeded());
+        }
+        self.depth += 1;
+        let r = f(self);
+        self.depth -= 1;
+        r
+    }
 }
 
 impl<R> ReadReader<R>
 where
     R: Read,
 {
+    #[allow(clippy::unbuffered_bytes)]
     fn from_read(r: R) -> Self {
         ReadReader {
             r: r.bytes(),
             ch: None,
             buf: Vec::new(),
+            max_line_len: usize::MAX,
+            lenient_line_endings: false,
+            record: None,
+            captures: 0,
         }
     }
 }
@@ -371,22 +746,22 @@ where
 impl<R: Read> Deserializer<ReadReader<R>> {
     /// Create a new [`Deserializer`] from an underlying Read
     pub fn from_read(r: R) -> Self {
-        Deserializer {
-            reader: ReadReader::from_read(r),
-            skip_attribute: true,
-            skip_push: true,
-        }
+        Deserializer::new(ReadReader::from_read(r))
+    }
+
+    /// Limits the length of lines (simple strings, simple errors) read from
+    /// the underlying Read, an unterminated line would otherwise be buffered
+    /// without bound. Slices are naturally bounded so it's not needed there.
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.reader.max_line_len = max_line_len;
+        self
     }
 }
 
 impl<'a, R: AsRef<[u8]> + ?Sized> Deserializer<RefReader<'a, R>> {
     /// Creates a new [`Deserializer`] from a slice of bytes
     pub fn from_slice(slice: &'a R) -> Self {
-        Deserializer {
-            reader: RefReader::from_slice(slice),
-            skip_attribute: true,
-            skip_push: true,
-        }
+        Deserializer::new(RefReader::from_slice(slice))
     }
 }
 
@@ -403,6 +778,138 @@ where
     pub fn get_consumed_bytes(&self) -> usize {
         self.reader.consumed_bytes()
     }
+
+    /// Like [`Deserializer::skip_value`] on a frame that may not be
+    /// complete yet, `scan` keeps the progress between calls. The slice
+    /// starts `scan.scanned` bytes into the frame, and on [`Error::EOF`]
+    /// `scan` stays after the last complete element so the next call,
+    /// with more bytes received, resumes from there.
+    pub(crate) fn scan_frame(&mut self, scan: &mut FrameScan) -> Result<()> {
+        let base = scan.scanned;
+        loop {
+            let peek = self.peek()?;
+            let streamed = matches!(scan.open.last(), Some(OpenAggregate { left: None, .. }));
+            let ended = if let Some(received) = scan.string {
+                // chunk of a streamed string
+                if peek != markers::STREAMED_STRING_CHUNK {
+                    return Err(Error::expected_marker_found(
+                        "';' for streamed string",
+                        peek,
+                    ));
+                }
+                self.reader.read_u8()?;
+                let len = self.read_length()?;
+                self.reader.read_crlf()?;
+                if len > self.options.max_length - received {
+                    return Err(Error::length_limit_exceeded());
+                }
+                if len == 0 {
+                    scan.string = None;
+                    Some(false)
+                } else {
+                    self.reader.read_slice(len, true)?;
+                    scan.string = Some(received + len);
+                    None
+                }
+            } else if peek == markers::STREAMED_AGGREGATE_END && streamed {
+                self.reader.read_u8()?;
+                self.reader.read_crlf()?;
+                scan.open.pop().map(|open| open.attribute)
+            } else if matches!(
+                peek,
+                markers::ARRAY | markers::SET | markers::PUSH | markers::MAP | markers::ATTRIBUTE
+            ) {
+                if scan.open.len() >= self.options.max_depth {
+                    return Err(Error::depth_limit_exceeded());
+                }
+                self.reader.read_u8()?;
+                let per_entry = if matches!(peek, markers::MAP | markers::ATTRIBUTE) {
+                    2
+                } else {
+                    1
+                };
+                let left = if self.read_streamed_marker()? {
+                    None
+                } else {
+                    let len = self.read_length()?;
+                    self.reader.read_crlf()?;
+                    let left = len.checked_mul(per_entry);
+                    Some(left.ok_or_else(Error::length_limit_exceeded)?)
+                };
+                let attribute = peek == markers::ATTRIBUTE;
+                if left == Some(0) {
+                    Some(attribute)
+                } else {
+                    scan.open.push(OpenAggregate { left, attribute });
+                    None
+                }
+            } else if matches!(
+                peek,
+                markers::BLOB_STRING | markers::BLOB_ERROR | markers::VERBATIM_STRING
+            ) {
+                self.reader.read_u8()?;
+                if self.read_streamed_marker()? {
+                    scan.string = Some(0);
+                    None
+                } else {
+                    self.parse_blob_string()?;
+                    Some(false)
+                }
+            } else {
+                self.skip_value()?;
+                Some(false)
+            };
+            scan.scanned = base + self.get_consumed_bytes();
+            if let Some(attribute) = ended {
+                if scan.end_element(attribute) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Progress of [`Deserializer::scan_frame`] through a frame received in
+/// parts, so the bytes already scanned aren't scanned again
+#[derive(Default)]
+pub(crate) struct FrameScan {
+    // bytes of the frame scanned so far
+    pub(crate) scanned: usize,
+    // aggregates not ended yet, innermost last
+    open: Vec<OpenAggregate>,
+    // bytes received of a streamed string not ended yet, scanned by chunk
+    string: Option<usize>,
+}
+
+struct OpenAggregate {
+    // values left, `None` for a streamed aggregate ended by `.`
+    left: Option<usize>,
+    attribute: bool,
+}
+
+impl FrameScan {
+    /// Records the end of an element, ending the aggregates it completes,
+    /// returns whether the frame is complete. An attribute isn't counted
+    /// as an element since the value it annotates follows.
+    fn end_element(&mut self, mut attribute: bool) -> bool {
+        loop {
+            if attribute {
+                return false;
+            }
+            let Some(open) = self.open.last_mut() else {
+                return true;
+            };
+            match &mut open.left {
+                Some(left) if *left > 1 => {
+                    *left -= 1;
+                    return false;
+                }
+                Some(_) => {}
+                None => return false,
+            }
+            attribute = self.open.pop().is_some_and(|open| open.attribute);
+        }
+    }
 }
 
 /// Deserialize an instance of type T from an I/O stream of RESP3
@@ -438,9 +945,123 @@ where
     T::deserialize(&mut d)
 }
 
+/// Nesting depth accepted by [`from_slice_checked`]
+const CHECKED_MAX_DEPTH: usize = 128;
+
+/// Deserialize an instance of type `T` from an untrusted slice, like
+/// [`from_slice`] but with limits so any input is either accepted or
+/// rejected with an error.
+///
+/// Malformed input never panics [`from_slice`] either, but nesting is
+/// unbounded there and deep enough input overflows the stack. Here the
+/// nesting depth is limited to 128 and length prefixes to the input length,
+/// see [`Deserializer::with_max_depth`] and [`Deserializer::with_max_length`]
+/// to pick other limits.
+///
+/// # Examples
+///
+/// ```rust
+/// let r: Vec<u64> = deseresp::from_slice_checked(b"*2\r\n:1\r\n:2\r\n").unwrap();
+/// assert_eq!(r, [1, 2]);
+///
+/// let nested = b"*1\r\n".repeat(100_000);
+/// assert!(deseresp::from_slice_checked::<_, deseresp::Value>(&nested).is_err());
+/// ```
+pub fn from_slice_checked<'a, R, T>(input: &'a R) -> Result<T>
+where
+    R: AsRef<[u8]> + ?Sized,
+    T: Deserialize<'a>,
+{
+    let options = Options {
+        max_depth: CHECKED_MAX_DEPTH,
+        max_length: input.as_ref().len(),
+        ..Options::default()
+    };
+    let mut d = Deserializer::from_slice(input).with_options(options);
+    T::deserialize(&mut d)
+}
+
+/// Deserialize an instance of type `T` from a slice, or the error replied by
+/// the server instead.
+///
+/// The outer [`Result`] reports malformed input, the inner one is `Err` when
+/// the reply is a simple error (`-`) or a blob error (`!`).
+///
+/// # Examples
+///
+/// ```rust
+/// let r: usize = deseresp::from_slice_result(b":42\r\n").unwrap().unwrap();
+/// assert_eq!(r, 42);
+///
+/// let r = deseresp::from_slice_result::<_, usize>(b"-ERR unknown\r\n").unwrap();
+/// assert_eq!(r.unwrap_err().0, "ERR unknown");
+/// ```
+pub fn from_slice_result<'a, R, T>(input: &'a R) -> Result<std::result::Result<T, SimpleError<'a>>>
+where
+    R: AsRef<[u8]> + ?Sized,
+    T: Deserialize<'a>,
+{
+    let mut d = Deserializer::from_slice(input);
+    match d.peek_skip_attribute()? {
+        markers::SIMPLE_ERROR => Ok(Err(SimpleError::deserialize(&mut d)?)),
+        markers::BLOB_ERROR => Ok(Err(SimpleError(BlobError::deserialize(&mut d)?.0))),
+        _ => T::deserialize(&mut d).map(Ok),
+    }
+}
+
 impl<'de, R: Reader<'de>> Deserializer<R> {
-    fn parse_blob_string<'a>(&'a mut self) -> Result<Reference<'de, 'a, [u8]>> {
+    /// Accepts a bare `\n` wherever `\r\n` terminates a line, for input
+    /// from hand-written fixtures or proxies that normalize line endings.
+    /// Disabled by default, strict RESP requires `\r\n`.
+    pub fn lenient_line_endings(mut self, lenient: bool) -> Self {
+        self.reader.set_lenient_line_endings(lenient);
+        self
+    }
+
+    /// Bounds the next value by skipping it, then deserializes `T` from its
+    /// bytes with the same options, so an error from `T` leaves the input
+    /// after the value rather than in the middle of it. The outer result
+    /// is for reading the value, the inner one for deserializing `T`.
+    /// `T` borrows from `frame`, which holds the value's bytes.
+    pub(crate) fn deserialize_bounded<'a, T>(&mut self, frame: &'a mut Vec<u8>) -> Result<Result<T>>
+    where
+        T: Deserialize<'a>,
+    {
+        *frame = self.capture(|de| de.skip_value())?;
+        let frame: &'a Vec<u8> = frame;
+        let mut de = self.fork(RefReader::from_slice(&frame[..]));
+        let value = T::deserialize(&mut de);
+        if let (Some(attributes), Some(read)) = (&mut self.attributes, de.attributes) {
+            attributes.extend(read);
+        }
+
+        Ok(value)
+    }
+
+    /// Creates a Deserializer over `reader` with the same options, line
+    /// endings and depth, collecting attributes if this one does
+    fn fork<'b, S: Reader<'b>>(&self, mut reader: S) -> Deserializer<S> {
+        reader.set_lenient_line_endings(self.reader.lenient_line_endings());
+        let mut de = Deserializer::new(reader).with_options(self.options);
+        de.depth = self.depth;
+        de.attributes = self.attributes.as_ref().map(|_| Vec::new());
+        de
+    }
+
+    fn read_length(&mut self) -> Result<usize> {
         let len = self.reader.read_length()?;
+        if len > self.options.max_length {
+            return Err(Error::length_limit_exceeded());
+        }
+
+        Ok(len)
+    }
+
+    fn parse_blob_string<'a>(&'a mut self) -> Result<Reference<'de, 'a, [u8]>> {
+        if self.read_streamed_marker()? {
+            return self.parse_streamed_string();
+        }
+        let len = self.read_length()?;
         self.reader.read_crlf()?;
 
         let slice = self.reader.read_slice(len, true)?;
@@ -448,6 +1069,32 @@ impl<'de, R: Reader<'de>> Deserializer<R> {
         Ok(slice)
     }
 
+    /// Joins the `;<len>` chunks of a streamed string (`$?`) up to the
+    /// empty one ending it, the joined length is bounded as a blob's
+    fn parse_streamed_string<'a>(&'a mut self) -> Result<Reference<'de, 'a, [u8]>> {
+        self.scratch.clear();
+        loop {
+            let peek = self.peek()?;
+            if peek != markers::STREAMED_STRING_CHUNK {
+                return Err(Error::expected_marker_found(
+                    "';' for streamed string",
+                    peek,
+                ));
+            }
+            self.reader.read_u8()?;
+            let len = self.read_length()?;
+            self.reader.read_crlf()?;
+            if len == 0 {
+                return Ok(Reference::Copied(&self.scratch[..]));
+            }
+            if len > self.options.max_length - self.scratch.len() {
+                return Err(Error::length_limit_exceeded());
+            }
+            let chunk = self.reader.read_slice(len, true)?;
+            self.scratch.extend_from_slice(&chunk);
+        }
+    }
+
     fn parse_simple_string<'a>(&'a mut self) -> Result<Reference<'de, 'a, [u8]>> {
         let slice = self
             .reader
@@ -463,38 +1110,415 @@ impl<'de, R: Reader<'de>> Deserializer<R> {
         Ok(val)
     }
 
-    fn skip_attribute(&mut self) -> Result<()> {
-        // TODO: fast skip by consuming all marker type
-        // instead of rely on consuming visitor
-        let _s: AttributeSkip = Deserialize::deserialize(self)?;
+    fn parse_integer_f32(&mut self) -> Result<f32> {
+        let bytes = self.parse_simple_string()?;
+        let digits = bytes.strip_prefix(b"-").unwrap_or(&bytes);
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return Err(Error::expected_value("number"));
+        }
+        let num: f32 = str_from_utf8(&bytes)?.parse().map_err(|_| Error::parse())?;
+        if num.is_infinite() {
+            return Err(Error::overflow());
+        }
 
-        Ok(())
+        Ok(num)
     }
 
-    fn skip_push(&mut self) -> Result<()> {
-        let _s: PushSkip = Deserialize::deserialize(self)?;
+    /// Reads an integer (`:`) or big number (`(`) as its sign and magnitude
+    fn parse_wide_integer(&mut self) -> Result<(bool, u128)> {
+        match self.peek_skip_attribute()? {
+            markers::INTEGER | markers::BIG_NUMBER => {
+                self.reader.read_u8()?;
+            }
+            found => {
+                return Err(Error::expected_marker_found(
+                    "':' or '(' for 128-bit integer",
+                    found,
+                ))
+            }
+        }
+        let negative = self.reader.peek_u8()? == Some(b'-');
+        if negative {
+            self.reader.read_u8()?;
+        }
+        let num: u128 = match self.reader.peek_u8()? {
+            Some(b'0'..=b'9') => self.reader.read_unsigned()?,
+            None => return Err(Error::eof()),
+            _ => return Err(Error::expected_value("number")),
+        };
+        self.reader.read_crlf()?;
 
-        Ok(())
+        Ok((negative, num))
     }
 
-    fn peek_skip_attribute(&mut self) -> Result<u8> {
-        let peek = self.peek()?;
+    /// Parses the body of the simple or blob string starting with `marker`
+    fn parse_string_number<T: str::FromStr>(&mut self, marker: u8) -> Result<T> {
+        self.reader.read_u8()?;
+        let bytes = if marker == markers::SIMPLE_STRING {
+            self.parse_simple_string()?
+        } else {
+            self.parse_blob_string()?
+        };
 
-        if peek == b'|' && self.skip_attribute {
-            self.skip_attribute()?;
-            return self.reader.peek_u8()?.ok_or_else(Error::eof);
-        }
-        if peek == b'>' && self.skip_push {
-            self.skip_push()?;
-            return self.reader.peek_u8()?.ok_or_else(Error::eof);
+        str_from_utf8(&bytes)?.parse().map_err(|_| Error::parse())
+    }
+
+    fn parse_integral_double(&mut self) -> Result<f64> {
+        let num = self.parse_double()?;
+        if !num.is_finite() || num.fract() != 0.0 {
+            return Err(Error::unexpected_value("non-integral double"));
         }
 
-        Ok(peek)
+        Ok(num)
     }
 
-    fn peek(&mut self) -> Result<u8> {
+    /// Visits an array, set or push, streamed ones (`*?`) up to their `.`
+    /// end. Arrays shorter than `tuple_len` are padded with missing
+    /// elements, which only deserialize into `None`, so trailing optional
+    /// tuple fields can be absent from the reply.
+    fn parse_seq<V>(&mut self, tuple_len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let peek = self.peek_skip_attribute()?;
+
+        match peek {
+            markers::ARRAY | markers::SET | markers::PUSH => {
+                self.reader.read_u8()?;
+            }
+            _ => {
+                return Err(Error::expected_marker_found(
+                    "'*', '~' or '>' for seq",
+                    peek,
+                ))
+            }
+        }
+        if self.read_streamed_marker()? {
+            return self.nested(|de| {
+                let mut seq = StreamedSeqAccess::new(de, tuple_len);
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            });
+        }
+        let len = self.read_length()?;
+        self.reader.read_crlf()?;
+        let padding = tuple_len.saturating_sub(len);
+        self.nested(|de| {
+            let mut seq = CountSeqAccess::new(de, len).with_padding(padding);
+            let value = visitor.visit_seq(&mut seq)?;
+            seq.end()?;
+            Ok(value)
+        })
+    }
+
+    /// Visits `len` key/value pairs, pairs left unread by the visitor are
+    /// skipped, or denied with [`Deserializer::deny_unread_elements`]
+    fn parse_map<V>(&mut self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.nested(|de| {
+            let mut map = CountMapAccess::new(de, len);
+            let value = visitor.visit_map(&mut map)?;
+            map.end()?;
+            Ok(value)
+        })
+    }
+
+    /// Visits the key/value pairs of a streamed map (`%?`) up to its `.` end,
+    /// pairs left unread are handled as in [`Deserializer::parse_map`]
+    fn parse_streamed_map<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.nested(|de| {
+            let mut map = StreamedMapAccess::new(de);
+            let value = visitor.visit_map(&mut map)?;
+            map.end()?;
+            Ok(value)
+        })
+    }
+
+    /// Consumes the next value, nested aggregates included, without
+    /// building a Rust value, e.g. to drain unwanted pipelined replies.
+    /// Attributes before the value are consumed along with it, streamed
+    /// strings and aggregates (`$?`, `*?`, ...) are supported.
+    pub fn skip_value(&mut self) -> Result<()> {
+        loop {
+            let marker = self.peek()?;
+            self.reader.read_u8()?;
+            match marker {
+                markers::SIMPLE_STRING
+                | markers::SIMPLE_ERROR
+                | markers::INTEGER
+                | markers::BIG_NUMBER
+                | markers::DOUBLE
+                | markers::BOOLEAN
+                | markers::NULL => {
+                    self.parse_simple_string()?;
+                }
+                markers::BLOB_STRING | markers::BLOB_ERROR | markers::VERBATIM_STRING => {
+                    if self.read_streamed_marker()? {
+                        self.skip_streamed_string()?;
+                    } else {
+                        self.parse_blob_string()?;
+                    }
+                }
+                markers::ARRAY
+                | markers::SET
+                | markers::PUSH
+                | markers::MAP
+                | markers::ATTRIBUTE => {
+                    self.skip_aggregate(marker)?;
+                    if marker == markers::ATTRIBUTE {
+                        continue;
+                    }
+                }
+                _ => return Err(Error::expected_value("type header")),
+            }
+            return Ok(());
+        }
+    }
+
+    /// Consumes the elements of an aggregate whose `marker` was just read,
+    /// streamed or not, without building a Rust value
+    fn skip_aggregate(&mut self, marker: u8) -> Result<()> {
+        let per_entry = if matches!(marker, markers::MAP | markers::ATTRIBUTE) {
+            2
+        } else {
+            1
+        };
+        let skip_entry = |de: &mut Self| (0..per_entry).try_for_each(|_| de.skip_value());
+        if self.read_streamed_marker()? {
+            return self.nested(|de| {
+                while de.peek()? != markers::STREAMED_AGGREGATE_END {
+                    skip_entry(de)?;
+                }
+                de.reader.read_u8()?;
+                de.reader.read_crlf()
+            });
+        }
+        let len = self.read_length()?;
+        self.reader.read_crlf()?;
+        self.nested(|de| (0..len).try_for_each(|_| skip_entry(de)))
+    }
+
+    /// Consumes the `?` length of a streamed string or aggregate
+    fn read_streamed_marker(&mut self) -> Result<bool> {
+        if self.reader.peek_u8()? != Some(b'?') {
+            return Ok(false);
+        }
+        self.reader.read_u8()?;
+        self.reader.read_crlf()?;
+
+        Ok(true)
+    }
+
+    /// Consumes `;<len>` chunks up to the empty one ending the string
+    fn skip_streamed_string(&mut self) -> Result<()> {
+        loop {
+            let peek = self.peek()?;
+            if peek != markers::STREAMED_STRING_CHUNK {
+                return Err(Error::expected_marker_found(
+                    "';' for streamed string",
+                    peek,
+                ));
+            }
+            self.reader.read_u8()?;
+            let len = self.read_length()?;
+            self.reader.read_crlf()?;
+            if len == 0 {
+                return Ok(());
+            }
+            self.reader.read_slice(len, true)?;
+        }
+    }
+
+    fn skip_attribute_frame(&mut self) -> Result<()> {
+        if self.attributes.is_some() {
+            return self.collect_attribute_frame();
+        }
+        self.reader.read_u8()?;
+        self.skip_aggregate(markers::ATTRIBUTE)
+    }
+
+    fn collect_attribute_frame(&mut self) -> Result<()> {
+        let frame = self.capture_attribute_frame()?;
+        if let Some(attributes) = &mut self.attributes {
+            attributes.push(frame);
+        }
+
+        Ok(())
+    }
+
+    /// Runs `f`, returning the bytes it consumed exactly as they were read
+    fn capture<F>(&mut self, f: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let mark = self.reader.start_capture();
+        let r = f(self);
+        let captured = self.reader.end_capture(mark);
+
+        r.map(|()| captured)
+    }
+
+    /// Consumes the attribute frame ahead, returning its bytes as received
+    fn capture_attribute_frame(&mut self) -> Result<Vec<u8>> {
+        self.capture(|de| {
+            de.reader.read_u8()?;
+            de.skip_aggregate(markers::ATTRIBUTE)
+        })
+    }
+
+    fn skip_push(&mut self) -> Result<()> {
+        self.reader.read_u8()?;
+        self.skip_aggregate(markers::PUSH)
+    }
+
+    fn peek_skip_attribute(&mut self) -> Result<u8> {
+        let peek = self.peek()?;
+
+        if peek == markers::ATTRIBUTE && self.options.skip_attribute {
+            self.skip_attribute_frame()?;
+            return self.reader.peek_u8()?.ok_or_else(Error::eof);
+        }
+        if peek == markers::PUSH && self.options.skip_push {
+            self.skip_push()?;
+            return self.reader.peek_u8()?.ok_or_else(Error::eof);
+        }
+
+        Ok(peek)
+    }
+
+    fn peek(&mut self) -> Result<u8> {
         self.reader.peek_u8()?.ok_or_else(Error::eof)
     }
+
+    /// Returns the RESP type of the next value without consuming it, to
+    /// branch before choosing the type to deserialize into.
+    ///
+    /// Attributes are skipped, pushes are reported as [`RespType::Push`]
+    /// since they are not part of the reply, see [`Deserializer::next_frame`].
+    pub fn peek_type(&mut self) -> Result<RespType> {
+        let mut peek = self.peek()?;
+        while peek == markers::ATTRIBUTE && self.options.skip_attribute {
+            self.skip_attribute_frame()?;
+            peek = self.peek()?;
+        }
+
+        RespType::from_marker(peek).ok_or_else(|| Error::expected_value("type header"))
+    }
+
+    /// Consumes the next marker if it is `marker`, e.g. [`markers::ARRAY`],
+    /// errors with [`Error::UnexpectedMarker`] carrying the marker found
+    /// otherwise, for parsers hand-written on top of the Deserializer.
+    ///
+    /// Attributes are skipped first, unless `marker` is
+    /// [`markers::ATTRIBUTE`].
+    ///
+    /// ```rust
+    /// use deseresp::{markers, Deserializer};
+    /// use serde::Deserialize;
+    ///
+    /// let mut d = Deserializer::from_slice(b"*2\r\n:1\r\n:2\r\n");
+    /// d.expect_marker(markers::ARRAY).unwrap();
+    /// let len = d.read_aggregate_len().unwrap();
+    /// let sum: u64 = (0..len).map(|_| u64::deserialize(&mut d).unwrap()).sum();
+    /// assert_eq!(sum, 3);
+    /// ```
+    pub fn expect_marker(&mut self, marker: u8) -> Result<()> {
+        let mut peek = self.peek()?;
+        while peek == markers::ATTRIBUTE
+            && self.options.skip_attribute
+            && marker != markers::ATTRIBUTE
+        {
+            self.skip_attribute_frame()?;
+            peek = self.peek()?;
+        }
+        if peek != marker {
+            return Err(Error::expected_marker_found(marker_label(marker), peek));
+        }
+        self.reader.read_u8()?;
+
+        Ok(())
+    }
+
+    /// Consumes the `<len>` line following an aggregate marker read with
+    /// [`Deserializer::expect_marker`], the elements are to be read next
+    pub fn read_aggregate_len(&mut self) -> Result<usize> {
+        let len = self.read_length()?;
+        self.reader.read_crlf()?;
+
+        Ok(len)
+    }
+
+    /// Checks the input is fully consumed, errors with [`Error::TrailingData`]
+    /// otherwise, carrying the number of bytes left for slices
+    pub fn expect_eof(&mut self) -> Result<()> {
+        match self.reader.peek_u8()? {
+            None => Ok(()),
+            Some(_) => Err(Error::trailing_data(self.reader.remaining())),
+        }
+    }
+
+    /// Reads an integer reply (`:<n>\r\n`) directly, without going through
+    /// serde, for clients reading many integer replies in a tight loop
+    pub fn read_integer_reply(&mut self) -> Result<i64> {
+        let peek = self.peek()?;
+        if peek != markers::INTEGER {
+            return Err(Error::expected_marker_found("':' for integer reply", peek));
+        }
+        self.reader.read_u8()?;
+        let num = match self.reader.peek_u8()? {
+            Some(b'-') => {
+                self.reader.read_u8()?;
+                let num: u64 = match self.reader.peek_u8()? {
+                    Some(b'0'..=b'9') => self.reader.read_unsigned()?,
+                    None => return Err(Error::eof()),
+                    _ => return Err(Error::expected_value("number")),
+                };
+                0i64.checked_sub_unsigned(num).ok_or_else(Error::overflow)?
+            }
+            Some(b'0'..=b'9') => {
+                let num: u64 = self.reader.read_unsigned()?;
+                i64::try_from(num).map_err(|_| Error::overflow())?
+            }
+            None => return Err(Error::eof()),
+            _ => return Err(Error::expected_value("number")),
+        };
+        self.reader.read_crlf()?;
+
+        Ok(num)
+    }
+
+    /// Reads the next value from the input as a [`Frame`], distinguishing
+    /// server pushes from command replies
+    pub fn next_frame(&mut self) -> Result<Frame> {
+        self.with_pushes(|de| {
+            let peek = de.peek_skip_attribute()?;
+            let value = Value::deserialize(&mut *de)?;
+            if peek == markers::PUSH {
+                Ok(Frame::Push(value))
+            } else {
+                Ok(Frame::Reply(value))
+            }
+        })
+    }
+
+    /// Runs `f` with pushes deserialized in place instead of skipped,
+    /// restoring the setting after
+    fn with_pushes<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let last_skip = self.options.skip_push;
+        self.options.skip_push = false;
+        let r = f(self);
+        self.options.skip_push = last_skip;
+        r
+    }
 }
 
 fn visit_ref_bytes<'de, 'a, V>(r: Reference<'de, 'a, [u8]>, visitor: V) -> Result<V::Value>
@@ -507,23 +1531,58 @@ where
     }
 }
 
+/// Narrows a double to f32, finite doubles out of the f32 range overflow
+/// instead of becoming infinite
+fn f64_to_f32(num: f64) -> Result<f32> {
+    let narrowed = num as f32;
+    if num.is_finite() && narrowed.is_infinite() {
+        return Err(Error::overflow());
+    }
+
+    Ok(narrowed)
+}
+
+/// Label of `marker` in marker errors, e.g. `'*' for array`
+fn marker_label(marker: u8) -> &'static str {
+    match marker {
+        markers::SIMPLE_STRING => "'+' for simple string",
+        markers::SIMPLE_ERROR => "'-' for simple error",
+        markers::BLOB_STRING => "'$' for blob string",
+        markers::BLOB_ERROR => "'!' for blob error",
+        markers::VERBATIM_STRING => "'=' for verbatim string",
+        markers::INTEGER => "':' for integer",
+        markers::DOUBLE => "',' for double",
+        markers::BOOLEAN => "'#' for boolean",
+        markers::BIG_NUMBER => "'(' for big number",
+        markers::NULL => "'_' for null",
+        markers::ARRAY => "'*' for array",
+        markers::SET => "'~' for set",
+        markers::PUSH => "'>' for push",
+        markers::MAP => "'%' for map",
+        markers::ATTRIBUTE => "'|' for attribute",
+        markers::STREAMED_STRING_CHUNK => "';' for streamed string chunk",
+        markers::STREAMED_AGGREGATE_END => "'.' for streamed aggregate end",
+        _ => "requested marker",
+    }
+}
+
 fn visit_ref_str<'de, 'a, V>(r: Reference<'de, 'a, [u8]>, visitor: V) -> Result<V::Value>
 where
     V: serde::de::Visitor<'de>,
 {
     match r {
         Reference::Copied(s) => {
-            let string = str::from_utf8(s).map_err(|e| Error::utf8(e.valid_up_to()))?;
+            let string = str_from_utf8(s)?;
             visitor.visit_str(string)
         }
         Reference::Borrowed(s) => {
-            let string = str::from_utf8(s).map_err(|e| Error::utf8(e.valid_up_to()))?;
+            let string = str_from_utf8(s)?;
             visitor.visit_borrowed_str(string)
         }
     }
 }
 
-impl<'de, 'a, R> serde::Deserializer<'de> for &'a mut Deserializer<R>
+impl<'de, R> serde::Deserializer<'de> for &mut Deserializer<R>
 where
     R: Reader<'de>,
 {
@@ -537,28 +1596,34 @@ where
 
         match peek {
             // blob string
-            b'$' => self.deserialize_str(visitor),
+            markers::BLOB_STRING => self.deserialize_str(visitor),
             // verbatim string
-            b'=' => self.deserialize_str(visitor),
+            markers::VERBATIM_STRING => self.deserialize_str(visitor),
             // blob error
-            b'!' => self.deserialize_str(visitor),
+            markers::BLOB_ERROR => self.deserialize_str(visitor),
             // simple string
-            b'+' => self.deserialize_str(visitor),
+            markers::SIMPLE_STRING => self.deserialize_str(visitor),
             // simple error
-            b'-' => self.deserialize_str(visitor),
+            markers::SIMPLE_ERROR => self.deserialize_str(visitor),
+            // big number
+            markers::BIG_NUMBER => self.deserialize_str(visitor),
+            // null
+            markers::NULL => self.deserialize_unit(visitor),
             // boolean
-            b'#' => self.deserialize_bool(visitor),
+            markers::BOOLEAN => self.deserialize_bool(visitor),
             // number
-            b':' => self.deserialize_i64(visitor),
+            markers::INTEGER => self.deserialize_i64(visitor),
             // floating point
-            b',' => self.deserialize_f64(visitor),
+            markers::DOUBLE => self.deserialize_f64(visitor),
             // array
-            b'*' => self.deserialize_seq(visitor),
-            b'~' => self.deserialize_seq(visitor),
-            b'>' => self.deserialize_seq(visitor),
+            markers::ARRAY => self.deserialize_seq(visitor),
+            // set, visited as a seq for Vec<T> and self-describing targets,
+            // `Value` tells it apart by reading through its own token
+            markers::SET => self.deserialize_seq(visitor),
+            markers::PUSH => self.deserialize_seq(visitor),
             // map
-            b'%' => self.deserialize_map(visitor),
-            b'|' => self.deserialize_map(visitor),
+            markers::MAP => self.deserialize_map(visitor),
+            markers::ATTRIBUTE => self.deserialize_map(visitor),
             _ => Err(Error::expected_value("type header")),
         }
     }
@@ -570,12 +1635,31 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b'#' => {
+            markers::BOOLEAN => {
                 self.reader.read_u8()?;
                 let val = self.reader.read_bool()?;
                 visitor.visit_bool(val)
             }
-            _ => Err(Error::expected_marker("bool")),
+            markers::INTEGER if self.options.resp2_compat => {
+                self.reader.read_u8()?;
+                let num: u64 = match self.reader.peek_u8()? {
+                    Some(b'0'..=b'9') => self.reader.read_unsigned()?,
+                    None => return Err(Error::eof()),
+                    _ => return Err(Error::expected_value("0|1")),
+                };
+                self.reader.read_crlf()?;
+                match num {
+                    0 => visitor.visit_bool(false),
+                    1 => visitor.visit_bool(true),
+                    _ => Err(Error::unexpected_value("integer bool other than 0|1")),
+                }
+            }
+            // RESP2 servers reply `:0`/`:1`, point at the option accepting them
+            markers::INTEGER => Err(Error::expected_marker_found(
+                "'#' for bool (`:0`/`:1` need resp2_compat)",
+                peek,
+            )),
+            _ => Err(Error::expected_marker_found("'#' for bool", peek)),
         }
     }
 
@@ -607,7 +1691,7 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b':' => {
+            markers::INTEGER => {
                 self.reader.read_u8()?;
                 match self.reader.peek_u8()? {
                     Some(b'-') => {
@@ -621,13 +1705,38 @@ where
                         self.reader.read_crlf()?;
                         visitor.visit_i64(num)
                     }
+                    None => Err(Error::eof()),
                     _ => Err(Error::expected_value("number")),
                 }
             }
-            _ => Err(Error::expected_marker("number")),
+            markers::DOUBLE if self.options.coerce_integral_double => {
+                self.reader.read_u8()?;
+                let num = self.parse_integral_double()?;
+                // i64::MAX as f64 rounds up to 2^63, which is out of range
+                if num < i64::MIN as f64 || num >= i64::MAX as f64 {
+                    return Err(Error::overflow());
+                }
+                visitor.visit_i64(num as i64)
+            }
+            markers::SIMPLE_STRING | markers::BLOB_STRING if self.options.coerce_string_number => {
+                visitor.visit_i64(self.parse_string_number(peek)?)
+            }
+            _ => Err(Error::expected_marker_found("':' for signed integer", peek)),
         }
     }
 
+    /// Also reads big numbers (`(`), erroring if out of i128 range
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let num = match self.parse_wide_integer()? {
+            (true, num) => 0i128.checked_sub_unsigned(num),
+            (false, num) => i128::try_from(num).ok(),
+        };
+        visitor.visit_i128(num.ok_or_else(Error::overflow)?)
+    }
+
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
@@ -656,7 +1765,7 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b':' => {
+            markers::INTEGER => {
                 self.reader.read_u8()?;
                 match self.reader.peek_u8()? {
                     Some(b'-') => Err(Error::unexpected_value("signed")),
@@ -665,18 +1774,67 @@ where
                         self.reader.read_crlf()?;
                         visitor.visit_u64(num)
                     }
+                    None => Err(Error::eof()),
                     _ => Err(Error::expected_value("number")),
                 }
             }
-            _ => Err(Error::expected_marker("number")),
+            markers::DOUBLE if self.options.coerce_integral_double => {
+                self.reader.read_u8()?;
+                let num = self.parse_integral_double()?;
+                if num < 0.0 {
+                    return Err(Error::unexpected_value("signed"));
+                }
+                if num >= u64::MAX as f64 {
+                    return Err(Error::overflow());
+                }
+                visitor.visit_u64(num as u64)
+            }
+            markers::SIMPLE_STRING | markers::BLOB_STRING if self.options.coerce_string_number => {
+                visitor.visit_u64(self.parse_string_number(peek)?)
+            }
+            _ => Err(Error::expected_marker_found(
+                "':' for unsigned integer",
+                peek,
+            )),
+        }
+    }
+
+    /// Also reads big numbers (`(`), erroring if out of u128 range
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.parse_wide_integer()? {
+            (true, _) => Err(Error::unexpected_value("signed")),
+            (false, num) => visitor.visit_u128(num),
         }
     }
 
+    /// Integers are rounded to the nearest f32 directly from their decimal
+    /// form, e.g. `:16777217` becomes `16777216.0`, and error if out of f32
+    /// range. Doubles are read as f64 then cast, as f64 is the RESP precision.
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        self.deserialize_f64(visitor)
+        let peek = self.peek_skip_attribute()?;
+
+        match peek {
+            markers::INTEGER => {
+                self.reader.read_u8()?;
+                let num = self.parse_integer_f32()?;
+                visitor.visit_f32(num)
+            }
+            markers::DOUBLE => {
+                self.reader.read_u8()?;
+                let num = self.parse_double()?;
+                visitor.visit_f32(f64_to_f32(num)?)
+            }
+            markers::SIMPLE_STRING | markers::BLOB_STRING if self.options.coerce_string_number => {
+                visitor.visit_f32(f64_to_f32(self.parse_string_number(peek)?)?)
+            }
+            _ => Err(Error::expected_marker_found("',' or ':' for f32", peek)),
+        }
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
@@ -686,29 +1844,36 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b':' => {
+            markers::INTEGER => {
                 self.reader.read_u8()?;
-                match self.reader.peek_u8()? {
-                    Some(b'-') => {
-                        self.reader.read_u8()?;
-                        let num: i64 = self.reader.read_unsigned()?;
-                        self.reader.read_crlf()?;
-                        visitor.visit_f64(-num as f64)
-                    }
-                    Some(b'0'..=b'9') => {
-                        let num: i64 = self.reader.read_unsigned()?;
-                        self.reader.read_crlf()?;
-                        visitor.visit_f64(num as f64)
-                    }
-                    _ => Err(Error::expected_value("number")),
+                let negative = self.reader.peek_u8()? == Some(b'-');
+                if negative {
+                    self.reader.read_u8()?;
+                }
+                let num: i64 = match self.reader.peek_u8()? {
+                    Some(b'0'..=b'9') => self.reader.read_unsigned()?,
+                    None => return Err(Error::eof()),
+                    _ => return Err(Error::expected_value("number")),
+                };
+                self.reader.read_crlf()?;
+                if self.options.deny_lossy_integer_double && num > 1 << f64::MANTISSA_DIGITS {
+                    return Err(Error::unexpected_value("integer not exact as double"));
+                }
+                if negative {
+                    visitor.visit_f64(-num as f64)
+                } else {
+                    visitor.visit_f64(num as f64)
                 }
             }
-            b',' => {
+            markers::DOUBLE => {
                 self.reader.read_u8()?;
                 let num = self.parse_double()?;
                 visitor.visit_f64(num)
             }
-            _ => Err(Error::expected_marker("number|double")),
+            markers::SIMPLE_STRING | markers::BLOB_STRING if self.options.coerce_string_number => {
+                visitor.visit_f64(self.parse_string_number(peek)?)
+            }
+            _ => Err(Error::expected_marker_found("',' or ':' for f64", peek)),
         }
     }
 
@@ -726,32 +1891,40 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b'+' => {
+            markers::SIMPLE_STRING => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_simple_string()?;
                 visit_ref_str(bytes, visitor)
             }
-            b'-' => {
+            markers::SIMPLE_ERROR => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_simple_string()?;
                 visit_ref_str(bytes, visitor)
             }
-            b'$' => {
+            markers::BLOB_STRING => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_blob_string()?;
                 visit_ref_str(bytes, visitor)
             }
-            b'!' => {
+            markers::BLOB_ERROR => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_blob_string()?;
                 visit_ref_str(bytes, visitor)
             }
-            b'=' => {
+            markers::VERBATIM_STRING => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_blob_string()?;
                 visit_ref_str(bytes, visitor)
             }
-            _ => Err(Error::expected_marker("string|error")),
+            markers::BIG_NUMBER => {
+                self.reader.read_u8()?;
+                let bytes = self.parse_simple_string()?;
+                visit_ref_str(bytes, visitor)
+            }
+            _ => Err(Error::expected_marker_found(
+                "string, error or big number for str",
+                peek,
+            )),
         }
     }
 
@@ -769,32 +1942,36 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b'+' => {
+            markers::SIMPLE_STRING => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_simple_string()?;
                 visit_ref_bytes(bytes, visitor)
             }
-            b'-' => {
+            markers::SIMPLE_ERROR => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_simple_string()?;
                 visit_ref_bytes(bytes, visitor)
             }
-            b'$' => {
+            markers::BLOB_STRING => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_blob_string()?;
                 visit_ref_bytes(bytes, visitor)
             }
-            b'!' => {
+            markers::BLOB_ERROR => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_blob_string()?;
                 visit_ref_bytes(bytes, visitor)
             }
-            b'=' => {
+            markers::VERBATIM_STRING => {
                 self.reader.read_u8()?;
                 let bytes = self.parse_blob_string()?;
                 visit_ref_bytes(bytes, visitor)
             }
-            _ => Err(Error::expected_marker("string|error")),
+            markers::ARRAY | markers::SET => self.parse_seq(0, visitor),
+            _ => Err(Error::expected_marker_found(
+                "string, error or array for bytes",
+                peek,
+            )),
         }
     }
 
@@ -813,7 +1990,7 @@ where
 
         match peek {
             // "_\r\n" => null
-            b'_' => {
+            markers::NULL => {
                 self.reader.read_u8()?;
                 self.reader.read_crlf()?;
                 visitor.visit_none()
@@ -829,12 +2006,12 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b'_' => {
+            markers::NULL => {
                 self.reader.read_u8()?;
                 self.reader.read_crlf()?;
                 visitor.visit_unit()
             }
-            _ => Err(Error::expected_marker("null")),
+            _ => Err(Error::expected_marker_found("'_' for unit", peek)),
         }
     }
 
@@ -853,60 +2030,78 @@ where
 
         match name {
             crate::types::SIMPLE_ERROR_TOKEN => {
-                if peek == b'-' {
+                if peek == markers::SIMPLE_ERROR {
                     self.reader.read_u8()?;
                     let bytes = self.parse_simple_string()?;
                     visit_ref_str(bytes, visitor)
                 } else {
-                    Err(Error::expected_marker("simple error"))
+                    Err(Error::expected_marker_found("'-' for SimpleError", peek))
                 }
             }
             crate::types::BLOB_ERROR_TOKEN => {
-                if peek == b'!' {
+                if peek == markers::BLOB_ERROR {
                     self.reader.read_u8()?;
                     let bytes = self.parse_blob_string()?;
                     visit_ref_str(bytes, visitor)
                 } else {
-                    Err(Error::expected_marker("blob error"))
+                    Err(Error::expected_marker_found("'!' for BlobError", peek))
                 }
             }
             crate::types::SIMPLE_STRING_TOKEN => {
-                if peek == b'+' {
+                if peek == markers::SIMPLE_STRING {
                     self.reader.read_u8()?;
                     let bytes = self.parse_simple_string()?;
                     visit_ref_str(bytes, visitor)
                 } else {
-                    Err(Error::expected_marker("simple string"))
+                    Err(Error::expected_marker_found("'+' for SimpleString", peek))
                 }
             }
             crate::types::BLOB_STRING_TOKEN => {
-                if peek == b'$' {
+                if peek == markers::BLOB_STRING {
                     self.reader.read_u8()?;
                     let bytes = self.parse_blob_string()?;
                     visit_ref_str(bytes, visitor)
                 } else {
-                    Err(Error::expected_marker("blob string"))
+                    Err(Error::expected_marker_found("'$' for BlobString", peek))
                 }
             }
-            crate::types::ATTRIBUTE_SKIP_TOKEN => {
-                if peek == b'|' {
-                    self.reader.read_u8()?;
-                    let len = self.reader.read_length()?;
-                    self.reader.read_crlf()?;
-                    visitor.visit_map(CountMapAccess::new(self, len))
-                } else {
-                    Err(Error::expected_marker("attribute"))
+            crate::types::PUSH_TOKEN => {
+                if peek != markers::PUSH {
+                    return Err(Error::expected_marker_found("'>' for Push", peek));
                 }
+                self.with_pushes(|de| visitor.visit_newtype_struct(de))
             }
-            crate::types::PUSH_TOKEN => {
-                if peek != b'>' {
-                    return Err(Error::expected_marker("push"));
+            crate::types::SET_TOKEN => {
+                let peek = self.peek_skip_attribute()?;
+                if peek != markers::SET {
+                    return Err(Error::expected_marker_found("'~' for Set", peek));
                 }
-                self.skip_push = false;
                 visitor.visit_newtype_struct(self)
             }
+            crate::types::RAW_TOKEN => {
+                self.peek_skip_attribute()?;
+                let start = self
+                    .reader
+                    .remaining_slice()
+                    .ok_or_else(|| Error::unexpected_value("raw frame from io reader"))?;
+                self.skip_value()?;
+                let rest = self.reader.remaining_slice().map_or(0, <[u8]>::len);
+                visitor.visit_borrowed_bytes(&start[..start.len() - rest])
+            }
+            crate::types::RESP_VALUE_TOKEN => {
+                let peek = self.peek_skip_attribute()?;
+                let kind =
+                    Kind::from_marker(peek).ok_or_else(|| Error::expected_value("type header"))?;
+                visitor.visit_enum(ValueAccess::new(self, kind))
+            }
+            crate::types::RESP_FRAME_TOKEN => self.with_pushes(|de| {
+                let peek = de.peek_skip_attribute()?;
+                let kind =
+                    Kind::from_marker(peek).ok_or_else(|| Error::expected_value("type header"))?;
+                visitor.visit_enum(ValueAccess::new(de, kind))
+            }),
             crate::types::PUSH_OR_VALUE_TOKEN => {
-                if peek == b'>' {
+                if peek == markers::PUSH {
                     visitor.visit_map(PushOrValueAccess::new_push(self))
                 } else {
                     visitor.visit_map(PushOrValueAccess::new_value(self))
@@ -920,43 +2115,20 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        let peek = self.peek_skip_attribute()?;
-
-        match peek {
-            b'*' => {
-                self.reader.read_u8()?;
-                let len = self.reader.read_length()?;
-                self.reader.read_crlf()?;
-                visitor.visit_seq(CountSeqAccess::new(self, len))
-            }
-            b'~' => {
-                self.reader.read_u8()?;
-                let len = self.reader.read_length()?;
-                self.reader.read_crlf()?;
-                visitor.visit_seq(CountSeqAccess::new(self, len))
-            }
-            b'>' => {
-                self.skip_push = true;
-                self.reader.read_u8()?;
-                let len = self.reader.read_length()?;
-                self.reader.read_crlf()?;
-                visitor.visit_seq(CountSeqAccess::new(self, len))
-            }
-            _ => Err(Error::expected_marker("array|set|push")),
-        }
+        self.parse_seq(0, visitor)
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.parse_seq(len, visitor)
     }
 
     fn deserialize_tuple_struct<V>(
         self,
         name: &'static str,
-        _len: usize,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value>
     where
@@ -966,17 +2138,60 @@ where
 
         match name {
             crate::types::WITH_ATTRIBUTE_TOKEN => {
-                if peek == b'|' {
-                    let last_skip = self.skip_attribute;
-                    self.skip_attribute = false;
-                    let r = visitor.visit_seq(CountSeqAccess::new(self, 2));
-                    self.skip_attribute = last_skip;
+                if peek == markers::ATTRIBUTE {
+                    let last_skip = self.options.skip_attribute;
+                    self.options.skip_attribute = false;
+                    let r = self.nested(|de| {
+                        visitor.visit_seq(WithAttributeSeqAccess {
+                            de,
+                            last_skip,
+                            len: 2,
+                        })
+                    });
+                    self.options.skip_attribute = last_skip;
                     r
                 } else {
-                    Err(Error::expected_marker("attribute"))
+                    Err(Error::expected_marker_found("'|' for WithAttribute", peek))
+                }
+            }
+            crate::types::WITH_RAW_ATTRIBUTE_TOKEN => {
+                if peek != markers::ATTRIBUTE {
+                    return Err(Error::expected_marker_found(
+                        "'|' for WithRawAttribute",
+                        peek,
+                    ));
+                }
+                let attr = self.capture_attribute_frame()?;
+                visitor.visit_seq(RawAttributeSeqAccess {
+                    de: self,
+                    attr: Some(attr),
+                    value: true,
+                })
+            }
+            crate::types::BOUNDED_STRING_TOKEN => {
+                let peek = self.peek_skip_attribute()?;
+                let bytes = match peek {
+                    markers::SIMPLE_STRING => {
+                        self.reader.read_u8()?;
+                        self.parse_simple_string()?
+                    }
+                    markers::BLOB_STRING => {
+                        self.reader.read_u8()?;
+                        self.parse_blob_string()?
+                    }
+                    _ => {
+                        return Err(Error::expected_marker_found(
+                            "'+' or '$' for BoundedString",
+                            peek,
+                        ))
+                    }
+                };
+                if bytes.len() > len {
+                    return Err(Error::length_limit_exceeded());
                 }
+                visit_ref_str(bytes, visitor)
             }
-            _ => self.deserialize_seq(visitor),
+            _ => self.parse_seq(len, visitor),
         }
     }
 
@@ -987,35 +2202,56 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b'%' => {
+            markers::MAP => {
                 self.reader.read_u8()?;
-                let len = self.reader.read_length()?;
+                if self.read_streamed_marker()? {
+                    return self.parse_streamed_map(visitor);
+                }
+                let len = self.read_length()?;
                 self.reader.read_crlf()?;
-                visitor.visit_map(CountMapAccess::new(self, len))
+                self.parse_map(len, visitor)
             }
-            b'|' => {
+            markers::ATTRIBUTE => {
                 self.reader.read_u8()?;
-                let len = self.reader.read_length()?;
+                let len = self.read_length()?;
                 self.reader.read_crlf()?;
-                let last_skip = self.skip_attribute;
-                self.skip_attribute = true;
-                let r = visitor.visit_map(CountMapAccess::new(self, len));
-                self.skip_attribute = last_skip;
+                let last_skip = self.options.skip_attribute;
+                self.options.skip_attribute = true;
+                let r = self.parse_map(len, visitor);
+                self.options.skip_attribute = last_skip;
                 r
             }
-            _ => Err(Error::expected_marker("map")),
+            _ => Err(Error::expected_marker_found("'%' or '|' for map", peek)),
         }
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.options.assume_ordered_map {
+            let peek = self.peek_skip_attribute()?;
+            if peek == markers::MAP {
+                self.reader.read_u8()?;
+                // streamed maps have no length to count fields against
+                if self.read_streamed_marker()? {
+                    return self.parse_streamed_map(visitor);
+                }
+                let len = self.read_length()?;
+                self.reader.read_crlf()?;
+                return self.nested(|de| {
+                    let mut map = OrderedStructAccess::new(de, len, fields);
+                    let value = visitor.visit_map(&mut map)?;
+                    map.end()?;
+                    Ok(value)
+                });
+            }
+        }
         self.deserialize_any(visitor)
     }
 
@@ -1031,61 +2267,79 @@ where
         let peek = self.peek_skip_attribute()?;
 
         match peek {
-            b'%' => {
+            markers::MAP => {
                 self.reader.read_u8()?;
-                let len = self.reader.read_length()?;
+                let len = self.read_length()?;
                 self.reader.read_crlf()?;
                 if len > 1 {
                     return Err(Error::expected_value("1-length map"));
                 }
-                visitor.visit_enum(VariantAccess::new(self))
+                self.nested(|de| visitor.visit_enum(VariantAccess::new(de)))
             }
-            b'+' => visitor.visit_enum(UnitVariantAccess::new(self)),
-            b'$' => visitor.visit_enum(UnitVariantAccess::new(self)),
-            _ => Err(Error::expected_marker("map")),
+            // tagged array: variant name followed by the variant's elements
+            markers::ARRAY | markers::PUSH => {
+                self.reader.read_u8()?;
+                let len = self.read_length()?;
+                self.reader.read_crlf()?;
+                self.nested(|de| visitor.visit_enum(SeqVariantAccess::new(de, len)))
+            }
+            markers::SIMPLE_STRING => visitor.visit_enum(UnitVariantAccess::new(self)),
+            markers::BLOB_STRING => visitor.visit_enum(UnitVariantAccess::new(self)),
+            _ => Err(Error::expected_marker_found(
+                "'%', '*', '>' or string for enum",
+                peek,
+            )),
         }
     }
 
+    /// Struct field and enum variant names, only simple and blob strings
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        match self.peek_skip_attribute()? {
+            markers::SIMPLE_STRING | markers::BLOB_STRING => self.deserialize_str(visitor),
+            found => Err(Error::expected_marker_found(
+                "'+' or '$' for identifier",
+                found,
+            )),
+        }
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        self.peek_skip_attribute()?;
+        self.skip_value()?;
+        visitor.visit_unit()
     }
 }
 
-struct CountSeqAccess<'a, R> {
+/// Yields the attribute, read as a map, then the attributed value, which
+/// skips its own attributes (if any) as the enclosing value would
+struct WithAttributeSeqAccess<'a, R> {
     de: &'a mut Deserializer<R>,
+    last_skip: bool,
     len: usize,
 }
 
-impl<'a, R> CountSeqAccess<'a, R> {
-    fn new(de: &'a mut Deserializer<R>, len: usize) -> Self {
-        CountSeqAccess { de, len }
-    }
-}
-
-impl<'de, 'a, R: Reader<'de> + 'a> serde::de::SeqAccess<'de> for CountSeqAccess<'a, R> {
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::SeqAccess<'de> for WithAttributeSeqAccess<'a, R> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        if self.len > 0 {
-            let result = seed.deserialize(&mut *self.de).map(Some);
-            self.len -= 1;
-
-            result
-        } else {
-            Ok(None)
+        match self.len {
+            0 => Ok(None),
+            len => {
+                if len == 1 {
+                    self.de.options.skip_attribute = self.last_skip;
+                }
+                self.len -= 1;
+                seed.deserialize(&mut *self.de).map(Some)
+            }
         }
     }
 
@@ -1094,39 +2348,394 @@ impl<'de, 'a, R: Reader<'de> + 'a> serde::de::SeqAccess<'de> for CountSeqAccess<
     }
 }
 
-struct CountMapAccess<'a, R> {
+/// Yields the captured attribute frame as bytes, then the attributed value
+struct RawAttributeSeqAccess<'a, R> {
     de: &'a mut Deserializer<R>,
-    len: usize,
+    attr: Option<Vec<u8>>,
+    value: bool,
 }
 
-impl<'a, R> CountMapAccess<'a, R> {
-    fn new(de: &'a mut Deserializer<R>, len: usize) -> Self {
-        CountMapAccess { de, len }
-    }
-}
-
-impl<'de, 'a, R: Reader<'de> + 'a> serde::de::MapAccess<'de> for CountMapAccess<'a, R> {
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::SeqAccess<'de> for RawAttributeSeqAccess<'a, R> {
     type Error = Error;
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
     where
-        K: serde::de::DeserializeSeed<'de>,
+        T: serde::de::DeserializeSeed<'de>,
     {
-        if self.len > 0 {
-            let key = seed.deserialize(&mut *self.de).map(Some);
-            self.len -= 1;
-            key
+        if let Some(attr) = self.attr.take() {
+            let de: serde::de::value::BytesDeserializer<Error> =
+                serde::de::value::BytesDeserializer::new(&attr);
+            seed.deserialize(de).map(Some)
+        } else if self.value {
+            self.value = false;
+            seed.deserialize(&mut *self.de).map(Some)
         } else {
             Ok(None)
         }
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
-    where
+    fn size_hint(&self) -> Option<usize> {
+        Some(usize::from(self.attr.is_some()) + usize::from(self.value))
+    }
+}
+
+struct CountSeqAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    len: usize,
+    padding: usize,
+}
+
+impl<'a, R> CountSeqAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, len: usize) -> Self {
+        CountSeqAccess {
+            de,
+            len,
+            padding: 0,
+        }
+    }
+
+    fn with_padding(mut self, padding: usize) -> Self {
+        self.padding = padding;
+        self
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::SeqAccess<'de> for CountSeqAccess<'a, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        if self.len > 0 {
+            let result = seed.deserialize(&mut *self.de).map(Some);
+            self.len -= 1;
+
+            result
+        } else if self.padding > 0 {
+            self.padding -= 1;
+            seed.deserialize(MissingElementDeserializer).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> CountSeqAccess<'a, R> {
+    /// Skips the elements left unread by the visitor
+    fn end(self) -> Result<()> {
+        if self.len > 0 && self.de.options.deny_unread_elements {
+            return Err(Error::unexpected_value("unread elements"));
+        }
+        for _ in 0..self.len {
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct StreamedSeqAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    // the `.` ending the seq was read
+    ended: bool,
+    // elements expected by a tuple target, padded when the seq ends early
+    padding: usize,
+}
+
+impl<'a, R> StreamedSeqAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, padding: usize) -> Self {
+        StreamedSeqAccess {
+            de,
+            ended: false,
+            padding,
+        }
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> StreamedSeqAccess<'a, R> {
+    /// Consumes the `.` ending the seq if it is next
+    fn read_end(&mut self) -> Result<bool> {
+        if self.de.peek()? != markers::STREAMED_AGGREGATE_END {
+            return Ok(false);
+        }
+        self.de.reader.read_u8()?;
+        self.de.reader.read_crlf()?;
+        self.ended = true;
+
+        Ok(true)
+    }
+
+    /// Skips the elements left unread by the visitor
+    fn end(mut self) -> Result<()> {
+        while !self.ended && !self.read_end()? {
+            if self.de.options.deny_unread_elements {
+                return Err(Error::unexpected_value("unread elements"));
+            }
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::SeqAccess<'de> for StreamedSeqAccess<'a, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        if !self.ended && !self.read_end()? {
+            self.padding = self.padding.saturating_sub(1);
+            seed.deserialize(&mut *self.de).map(Some)
+        } else if self.padding > 0 {
+            self.padding -= 1;
+            seed.deserialize(MissingElementDeserializer).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+struct CountMapAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    len: usize,
+    // a key was read but not its value
+    pending_value: bool,
+}
+
+impl<'a, R> CountMapAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, len: usize) -> Self {
+        CountMapAccess {
+            de,
+            len,
+            pending_value: false,
+        }
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> CountMapAccess<'a, R> {
+    /// Skips the pairs left unread by the visitor
+    fn end(self) -> Result<()> {
+        if (self.len > 0 || self.pending_value) && self.de.options.deny_unread_elements {
+            return Err(Error::unexpected_value("unread elements"));
+        }
+        if self.pending_value {
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+        // the length comes from the input, a pair count can't be doubled safely
+        for _ in 0..self.len {
+            AnySkip::deserialize(&mut *self.de)?;
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::MapAccess<'de> for CountMapAccess<'a, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        if self.len > 0 {
+            let key = seed.deserialize(&mut *self.de).map(Some);
+            self.len -= 1;
+            self.pending_value = true;
+            key
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        self.pending_value = false;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct StreamedMapAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    // the `.` ending the map was read
+    ended: bool,
+    // a key was read but not its value
+    pending_value: bool,
+}
+
+impl<'a, R> StreamedMapAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>) -> Self {
+        StreamedMapAccess {
+            de,
+            ended: false,
+            pending_value: false,
+        }
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> StreamedMapAccess<'a, R> {
+    /// Consumes the `.` ending the map if it is next
+    fn read_end(&mut self) -> Result<bool> {
+        if self.de.peek()? != markers::STREAMED_AGGREGATE_END {
+            return Ok(false);
+        }
+        self.de.reader.read_u8()?;
+        self.de.reader.read_crlf()?;
+        self.ended = true;
+
+        Ok(true)
+    }
+
+    /// Skips the pairs left unread by the visitor
+    fn end(mut self) -> Result<()> {
+        if self.pending_value {
+            if self.de.options.deny_unread_elements {
+                return Err(Error::unexpected_value("unread elements"));
+            }
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+        while !self.ended && !self.read_end()? {
+            if self.de.options.deny_unread_elements {
+                return Err(Error::unexpected_value("unread elements"));
+            }
+            AnySkip::deserialize(&mut *self.de)?;
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::MapAccess<'de> for StreamedMapAccess<'a, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        if self.ended || self.read_end()? {
+            return Ok(None);
+        }
+        self.pending_value = true;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        self.pending_value = false;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// MapAccess that assigns struct fields positionally as long as the keys
+/// arrive in the declared order, falls back to key matching otherwise
+struct OrderedStructAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    len: usize,
+    fields: &'static [&'static str],
+    index: usize,
+    in_order: bool,
+    // a key was read but not its value
+    pending_value: bool,
+}
+
+impl<'a, R> OrderedStructAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, len: usize, fields: &'static [&'static str]) -> Self {
+        OrderedStructAccess {
+            de,
+            len,
+            fields,
+            index: 0,
+            in_order: true,
+            pending_value: false,
+        }
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> OrderedStructAccess<'a, R> {
+    /// Skips the pairs left unread by the visitor
+    fn end(self) -> Result<()> {
+        if (self.len > 0 || self.pending_value) && self.de.options.deny_unread_elements {
+            return Err(Error::unexpected_value("unread elements"));
+        }
+        if self.pending_value {
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+        for _ in 0..self.len {
+            AnySkip::deserialize(&mut *self.de)?;
+            AnySkip::deserialize(&mut *self.de)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::MapAccess<'de> for OrderedStructAccess<'a, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        self.pending_value = true;
+
+        let peek = self.de.peek_skip_attribute()?;
+        if !self.in_order || (peek != markers::SIMPLE_STRING && peek != markers::BLOB_STRING) {
+            return seed.deserialize(&mut *self.de).map(Some);
+        }
+
+        self.de.reader.read_u8()?;
+        let bytes = if peek == markers::SIMPLE_STRING {
+            self.de.parse_simple_string()?
+        } else {
+            self.de.parse_blob_string()?
+        };
+        let expected = self.fields.get(self.index);
+        if expected.map(|f| f.as_bytes()) == Some(&*bytes) {
+            let index = self.index as u64;
+            self.index += 1;
+            return seed.deserialize(index.into_deserializer()).map(Some);
+        }
+
+        self.in_order = false;
+        match bytes {
+            Reference::Borrowed(b) => {
+                let s = str_from_utf8(b)?;
+                seed.deserialize(BorrowedStrDeserializer::new(s)).map(Some)
+            }
+            Reference::Copied(b) => {
+                let s = str_from_utf8(b)?;
+                seed.deserialize(s.into_deserializer()).map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
         V: serde::de::DeserializeSeed<'de>,
     {
+        self.pending_value = false;
         seed.deserialize(&mut *self.de)
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
 }
 
 struct VariantAccess<'a, R> {
@@ -1243,79 +2852,251 @@ impl<'de, 'a, R: Reader<'de> + 'a> serde::de::VariantAccess<'de> for UnitVariant
     }
 }
 
-struct PushOrValueAccess<'a, R> {
-    de: &'a mut Deserializer<R>,
-    is_push: bool,
-    done: bool,
+/// Array or push whose first element is the variant name, the remaining
+/// elements fill the variant positionally
+struct SeqVariantAccess<'a, R> {
+    seq: CountSeqAccess<'a, R>,
 }
 
-impl<'a, R> PushOrValueAccess<'a, R> {
-    fn new_push(de: &'a mut Deserializer<R>) -> Self {
-        PushOrValueAccess { de, is_push: true, done: false }
-    }
-
-    fn new_value(de: &'a mut Deserializer<R>) -> Self {
-        PushOrValueAccess { de, is_push: false, done: false }
+impl<'a, R> SeqVariantAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, len: usize) -> Self {
+        SeqVariantAccess {
+            seq: CountSeqAccess::new(de, len),
+        }
     }
 }
 
-struct ConstantStrDeserializer {
-    s: &'static str
-}
-
-impl<'de> serde::de::Deserializer<'de> for ConstantStrDeserializer {
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::EnumAccess<'de> for SeqVariantAccess<'a, R> {
     type Error = Error;
+    type Variant = Self;
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
     where
-        V: serde::de::Visitor<'de>
+        V: serde::de::DeserializeSeed<'de>,
     {
-        visitor.visit_borrowed_str(self.s)
-    }
-
-    serde::forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf option unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+        let variant_key = serde::de::SeqAccess::next_element_seed(&mut self.seq, seed)?
+            .ok_or_else(|| Error::expected_value("variant name"))?;
+        Ok((variant_key, self))
     }
 }
 
-impl<'de, 'a, R: Reader<'de> + 'a> serde::de::MapAccess<'de> for PushOrValueAccess<'a, R> {
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::VariantAccess<'de> for SeqVariantAccess<'a, R> {
     type Error = Error;
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    fn unit_variant(self) -> Result<()> {
+        self.seq.end()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
     where
-        K: serde::de::DeserializeSeed<'de>,
+        T: serde::de::DeserializeSeed<'de>,
     {
-        if self.done {
-            return Ok(None);
-        }
-        if self.is_push {
-            Ok(Some(seed.deserialize(ConstantStrDeserializer { s: crate::types::PUSH_TOKEN })?))
-        } else {
-            Ok(Some(seed.deserialize(ConstantStrDeserializer { s: crate::types::VALUE_TOKEN })?))
-        }
+        let mut seq = self.seq.with_padding(1);
+        let value = serde::de::SeqAccess::next_element_seed(&mut seq, seed)?;
+        seq.end()?;
+        value.ok_or_else(|| Error::expected_value("element"))
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
-        V: serde::de::DeserializeSeed<'de>,
+        V: serde::de::Visitor<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        let padding = len.saturating_sub(self.seq.len);
+        let mut seq = self.seq.with_padding(padding);
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.tuple_variant(fields.len(), visitor)
     }
 }
 
-#[cfg(test)]
-#[allow(clippy::bool_assert_comparison)]
-mod tests {
-    use std::collections::HashMap;
+/// EnumAccess tagging the next value with its RESP type
+struct ValueAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    kind: Kind,
+}
 
-    use serde::Deserialize;
+impl<'a, R> ValueAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, kind: Kind) -> Self {
+        ValueAccess { de, kind }
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::EnumAccess<'de> for ValueAccess<'a, R> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let kind = seed.deserialize(ConstantStrDeserializer {
+            s: self.kind.name(),
+        })?;
+        Ok((kind, self))
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::VariantAccess<'de> for ValueAccess<'a, R> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        serde::de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        if self.kind == Kind::Push {
+            return self.de.with_pushes(|de| seed.deserialize(de));
+        }
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        Err(serde::de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        Err(serde::de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+struct PushOrValueAccess<'a, R> {
+    de: &'a mut Deserializer<R>,
+    is_push: bool,
+    done: bool,
+}
+
+impl<'a, R> PushOrValueAccess<'a, R> {
+    fn new_push(de: &'a mut Deserializer<R>) -> Self {
+        PushOrValueAccess {
+            de,
+            is_push: true,
+            done: false,
+        }
+    }
+
+    fn new_value(de: &'a mut Deserializer<R>) -> Self {
+        PushOrValueAccess {
+            de,
+            is_push: false,
+            done: false,
+        }
+    }
+}
+
+struct ConstantStrDeserializer {
+    s: &'static str,
+}
+
+impl<'de> serde::de::Deserializer<'de> for ConstantStrDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.s)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Stands for an element absent from a too short array
+struct MissingElementDeserializer;
+
+impl<'de> serde::de::Deserializer<'de> for MissingElementDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        Err(Error::expected_value("element"))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_none()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a, R: Reader<'de> + 'a> serde::de::MapAccess<'de> for PushOrValueAccess<'a, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        if self.is_push {
+            Ok(Some(seed.deserialize(ConstantStrDeserializer {
+                s: crate::types::PUSH_TOKEN,
+            })?))
+        } else {
+            Ok(Some(seed.deserialize(ConstantStrDeserializer {
+                s: crate::types::VALUE_TOKEN,
+            })?))
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Deserialize;
 
     use super::*;
     use crate::{
         test_utils::{test_deserialize, test_deserialize_result},
-        types::owned::{BlobError, BlobString, SimpleError, SimpleString},
+        types::{
+            owned::{BlobError, BlobString, SimpleError, SimpleString},
+            AnySkip, Push, WithAttribute,
+        },
+        Value,
     };
 
     #[test]
@@ -1329,8 +3110,27 @@ mod tests {
         });
 
         test_deserialize_result(b"+hello world\r\n", |value: Result<BlobString>| {
-            assert!(matches!(value, Err(_)));
+            assert!(value.is_err());
+        });
+    }
+
+    #[test]
+    fn test_streamed_string() {
+        let input = b"$?\r\n;5\r\nhello\r\n;6\r\n world\r\n;0\r\n";
+        test_deserialize(input, |value: String| {
+            assert_eq!(value, "hello world");
+        });
+        test_deserialize(b"$?\r\n;0\r\n", |value: String| {
+            assert_eq!(value, "");
         });
+        test_deserialize_result(b"$?\r\n;2\r\nhi\r\n+hi\r\n", |value: Result<String>| {
+            assert!(value.is_err());
+        });
+
+        // the joined length is limited, not only each chunk
+        let mut d = Deserializer::from_slice(input).with_max_length(8);
+        let value: Result<String> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
     }
 
     #[test]
@@ -1369,6 +3169,38 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_resp2_bool() {
+        let mut d = Deserializer::from_slice(b":1\r\n").resp2_compat(true);
+        let value: bool = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, true);
+
+        let mut d = Deserializer::from_read(&b":0\r\n"[..]).resp2_compat(true);
+        let value: bool = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, false);
+
+        let mut d = Deserializer::from_slice(b":2\r\n").resp2_compat(true);
+        let value: Result<bool> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(b":-1\r\n").resp2_compat(true);
+        let value: Result<bool> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(b":1\r\n");
+        let value: Result<bool> = Deserialize::deserialize(&mut d);
+        assert_eq!(
+            value.unwrap_err().to_string(),
+            "expected '#' for bool (`:0`/`:1` need resp2_compat), found ':'"
+        );
+
+        let value: Result<bool> = from_slice(b"+1\r\n");
+        assert_eq!(
+            value.unwrap_err().to_string(),
+            "expected '#' for bool, found '+'"
+        );
+    }
+
     #[test]
     fn test_number() {
         test_deserialize(b":12345\r\n", |value: i64| {
@@ -1380,6 +3212,61 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_wide_number() {
+        test_deserialize_result(
+            b"(3492890328409238509324850943850943825024385\r\n",
+            |value: Result<i128>| {
+                assert_eq!(value.unwrap_err(), Error::overflow());
+            },
+        );
+        test_deserialize(
+            b"(-170141183460469231731687303715884105728\r\n",
+            |value: i128| {
+                assert_eq!(value, i128::MIN);
+            },
+        );
+        test_deserialize(
+            b"(170141183460469231731687303715884105727\r\n",
+            |value: i128| {
+                assert_eq!(value, i128::MAX);
+            },
+        );
+        test_deserialize_result(
+            b"(170141183460469231731687303715884105728\r\n",
+            |value: Result<i128>| {
+                assert_eq!(value.unwrap_err(), Error::overflow());
+            },
+        );
+        test_deserialize(
+            b"(340282366920938463463374607431768211455\r\n",
+            |value: u128| {
+                assert_eq!(value, u128::MAX);
+            },
+        );
+        test_deserialize_result(
+            b"(340282366920938463463374607431768211456\r\n",
+            |value: Result<u128>| {
+                assert_eq!(value.unwrap_err(), Error::overflow());
+            },
+        );
+        test_deserialize_result(b"(-1\r\n", |value: Result<u128>| {
+            assert_eq!(value.unwrap_err(), Error::UnexpectedValue("signed"));
+        });
+        test_deserialize(b":-12345\r\n", |value: i128| {
+            assert_eq!(value, -12345);
+        });
+        test_deserialize(b":12345\r\n", |value: u128| {
+            assert_eq!(value, 12345);
+        });
+        test_deserialize_result(b"+12345\r\n", |value: Result<i128>| {
+            assert_eq!(
+                value.unwrap_err(),
+                Error::expected_marker_found("':' or '(' for 128-bit integer", b'+')
+            );
+        });
+    }
+
     #[test]
     fn test_double() {
         test_deserialize(b",1.23\r\n", |value: f64| {
@@ -1390,6 +3277,10 @@ mod tests {
             assert_eq!(value, 10.0);
         });
 
+        test_deserialize(b",-1.23\r\n", |value: f64| {
+            assert_eq!(value, -1.23);
+        });
+
         test_deserialize(b",inf\r\n", |value: f64| {
             assert_eq!(value, f64::INFINITY);
         });
@@ -1397,78 +3288,1405 @@ mod tests {
         test_deserialize(b",-inf\r\n", |value: f64| {
             assert_eq!(value, f64::NEG_INFINITY);
         });
-    }
 
-    #[test]
-    fn test_char() {
-        test_deserialize(b"+a\r\n", |value: char| {
-            assert_eq!(value, 'a');
+        test_deserialize(b",nan\r\n", |value: f64| {
+            assert!(value.is_nan());
+        });
+
+        test_deserialize(b",1.5e-3\r\n", |value: f64| {
+            assert_eq!(value, 0.0015);
+        });
+
+        test_deserialize(b",1E10\r\n", |value: f64| {
+            assert_eq!(value, 1e10);
+        });
+
+        test_deserialize(b",-3.0e+3\r\n", |value: f64| {
+            assert_eq!(value, -3000.0);
         });
     }
 
     #[test]
-    fn test_seq() {
-        test_deserialize(b"*3\r\n:1\r\n:2\r\n:3\r\n", |value: Vec<u64>| {
-            assert_eq!(value, [1, 2, 3]);
+    fn test_integer_to_double() {
+        // 2^53 + 1 rounds to 2^53
+        let input = b":9007199254740993\r\n";
+        test_deserialize(input, |value: f64| {
+            assert_eq!(value, 9007199254740992.0);
         });
 
-        test_deserialize(
-            b"*2\r\n*3\r\n:1\r\n$5\r\nhello\r\n:2\r\n#f\r\n",
-            |value: ((u64, String, u64), bool)| {
-                assert_eq!(value, ((1, String::from("hello"), 2), false));
-            },
+        let mut d = Deserializer::from_slice(input).deny_lossy_integer_double(true);
+        let value: Result<f64> = Deserialize::deserialize(&mut d);
+        assert_eq!(
+            value.unwrap_err(),
+            Error::UnexpectedValue("integer not exact as double")
         );
+
+        let mut d =
+            Deserializer::from_slice(b":-9007199254740993\r\n").deny_lossy_integer_double(true);
+        let value: Result<f64> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d =
+            Deserializer::from_slice(b":-9007199254740992\r\n").deny_lossy_integer_double(true);
+        let value: f64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, -9007199254740992.0);
     }
 
     #[test]
-    fn test_map() {
-        test_deserialize(
-            b"%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n",
-            |value: HashMap<String, usize>| {
-                let kv = value.into_iter().collect::<Vec<_>>();
-                assert!(kv.contains(&("first".to_string(), 1)));
-                assert!(kv.contains(&("second".to_string(), 2)));
-            },
+    fn test_malformed_double() {
+        for input in [
+            &b",1.2.3\r\n"[..],
+            b",+5\r\n",
+            b",\r\n",
+            b",-\r\n",
+            b",-inf3\r\n",
+            b",infinity\r\n",
+            b",-nan\r\n",
+            b",NaN\r\n",
+            b",1.\r\n",
+            b",.5\r\n",
+            b",1e\r\n",
+            b",1 \r\n",
+            b",0x10\r\n",
+        ] {
+            test_deserialize_result(input, |value: Result<f64>| {
+                assert_eq!(value.unwrap_err(), Error::Parse, "{:?}", input);
+            });
+        }
+    }
+
+    #[test]
+    fn test_coerce_integral_double() {
+        let mut d = Deserializer::from_slice(b",10\r\n").coerce_integral_double(true);
+        let value: i64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 10);
+
+        let mut d = Deserializer::from_read(&b",-10\r\n"[..]).coerce_integral_double(true);
+        let value: i32 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, -10);
+
+        let mut d = Deserializer::from_slice(b",1e3\r\n").coerce_integral_double(true);
+        let value: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 1000);
+
+        let mut d = Deserializer::from_slice(b",10.5\r\n").coerce_integral_double(true);
+        let value: Result<i64> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(b",inf\r\n").coerce_integral_double(true);
+        let value: Result<i64> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(b",-1\r\n").coerce_integral_double(true);
+        let value: Result<u64> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(b",1e19\r\n").coerce_integral_double(true);
+        let value: Result<i64> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(b",10\r\n");
+        let value: Result<i64> = Deserialize::deserialize(&mut d);
+        let err = Error::expected_marker_found("':' for signed integer", b',');
+        assert_eq!(value.unwrap_err(), err);
+    }
+
+    #[test]
+    fn test_coerce_string_number() {
+        fn coerce<'de, T: Deserialize<'de>>(input: &'de [u8]) -> Result<T> {
+            let mut d = Deserializer::from_slice(input).coerce_string_number(true);
+            T::deserialize(&mut d)
+        }
+
+        assert_eq!(coerce::<f64>(b"$4\r\n1.23\r\n").unwrap(), 1.23);
+        assert_eq!(coerce::<i64>(b"$2\r\n42\r\n").unwrap(), 42);
+        assert_eq!(coerce::<i32>(b"+-7\r\n").unwrap(), -7);
+        assert_eq!(coerce::<u8>(b"$3\r\n255\r\n").unwrap(), 255);
+        assert_eq!(coerce::<f32>(b"+0.5\r\n").unwrap(), 0.5);
+        assert_eq!(coerce::<f32>(b"+1e300\r\n").unwrap_err(), Error::overflow());
+        assert_eq!(coerce::<f64>(b"$4\r\n-inf\r\n").unwrap(), f64::NEG_INFINITY);
+        assert_eq!(coerce::<i64>(b":42\r\n").unwrap(), 42);
+        // ZSCORE of a missing member
+        assert_eq!(coerce::<Option<f64>>(b"_\r\n").unwrap(), None);
+
+        let mut d = Deserializer::from_read(&b"$4\r\n1.23\r\n"[..]).coerce_string_number(true);
+        assert_eq!(f64::deserialize(&mut d).unwrap(), 1.23);
+
+        assert_eq!(coerce::<i64>(b"$3\r\nabc\r\n").unwrap_err(), Error::Parse);
+        assert_eq!(coerce::<i64>(b"$3\r\n1.5\r\n").unwrap_err(), Error::Parse);
+        assert_eq!(coerce::<u64>(b"+-1\r\n").unwrap_err(), Error::Parse);
+        assert!(coerce::<u8>(b"+256\r\n").is_err());
+
+        let mut d = Deserializer::from_slice(b"$2\r\n42\r\n");
+        let value: Result<i64> = Deserialize::deserialize(&mut d);
+        let err = Error::expected_marker_found("':' for signed integer", b'$');
+        assert_eq!(value.unwrap_err(), err);
+    }
+
+    #[test]
+    fn test_peek_type() {
+        let input = b"-ERR x\r\n:1\r\n|1\r\n+a\r\n:1\r\n|1\r\n+b\r\n:2\r\n%0\r\n\
+            >1\r\n_\r\n!1\r\nE\r\n(1\r\n";
+        let expected = [
+            RespType::Error,
+            RespType::Integer,
+            RespType::Map,
+            RespType::Push,
+            RespType::Error,
+            RespType::BigNumber,
+        ];
+        let mut d = Deserializer::from_slice(input);
+        let mut read_d = Deserializer::from_read(&input[..]);
+        for resp_type in expected {
+            assert_eq!(d.peek_type().unwrap(), resp_type);
+            // peeking again doesn't consume
+            assert_eq!(d.peek_type().unwrap(), resp_type);
+            d.next_frame().unwrap();
+            assert_eq!(read_d.peek_type().unwrap(), resp_type);
+            read_d.next_frame().unwrap();
+        }
+        assert_eq!(d.peek_type().unwrap_err(), Error::EOF);
+
+        // branch on an error reply
+        let mut d = Deserializer::from_slice(b"-ERR unknown\r\n");
+        assert_eq!(d.peek_type().unwrap(), RespType::Error);
+        let err: SimpleError = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(err.0, "ERR unknown");
+
+        let mut d = Deserializer::from_slice(b"?\r\n");
+        assert_eq!(
+            d.peek_type().unwrap_err(),
+            Error::ExpectedValue("type header")
         );
+    }
 
-        #[derive(PartialEq, Deserialize, Debug)]
-        struct CustomMap {
-            first: usize,
-            second: f64,
+    #[test]
+    fn test_push_then_skipped_push() {
+        // reading a push doesn't keep later pushes from being skipped
+        let input = b">2\r\n+message\r\n+a\r\n>1\r\n+b\r\n+OK\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let mut read_d = Deserializer::from_read(&input[..]);
+        let push: Push<Value> = Deserialize::deserialize(&mut d).unwrap();
+        let message = vec![
+            Value::SimpleString("message".into()),
+            Value::SimpleString("a".into()),
+        ];
+        assert_eq!(push.0, Value::Push(message.clone()));
+        let reply: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(reply, "OK");
+
+        let push: Push<Value> = Deserialize::deserialize(&mut read_d).unwrap();
+        assert_eq!(push.0, Value::Push(message));
+        let reply: String = Deserialize::deserialize(&mut read_d).unwrap();
+        assert_eq!(reply, "OK");
+
+        let mut d = Deserializer::from_slice(input);
+        assert!(matches!(d.next_frame().unwrap(), Frame::Push(_)));
+        let reply: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(reply, "OK");
+    }
+
+    #[test]
+    fn test_ignored_any() {
+        let input = b"+a\r\n-b\r\n:1\r\n(123\r\n,1.5\r\n#t\r\n_\r\n$1\r\nc\r\n!1\r\nd\r\n\
+            =7\r\ntxt:abc\r\n*2\r\n:1\r\n~1\r\n+x\r\n%1\r\n+k\r\n*0\r\n\
+            |1\r\n+a\r\n:1\r\n*1\r\n>1\r\n:2\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let mut read_d = Deserializer::from_read(&input[..]);
+        for _ in 0..13 {
+            serde::de::IgnoredAny::deserialize(&mut d).unwrap();
+            serde::de::IgnoredAny::deserialize(&mut read_d).unwrap();
+        }
+        d.expect_eof().unwrap();
+        assert_eq!(read_d.peek_type().unwrap_err(), Error::EOF);
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Reply {
+            id: u64,
         }
         test_deserialize(
-            b"%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n",
-            |value: CustomMap| {
-                assert_eq!(
-                    value,
-                    CustomMap {
-                        first: 1,
-                        second: 2.0
-                    }
-                );
-            },
+            b"%3\r\n+skip\r\n*2\r\n%1\r\n+a\r\n*1\r\n:1\r\n~0\r\n\
+                +id\r\n:7\r\n+also\r\n|1\r\n+a\r\n:1\r\n$3\r\nabc\r\n",
+            |value: Reply| assert_eq!(value, Reply { id: 7 }),
         );
+
+        let mut d = Deserializer::from_slice(b"*1\r\n?\r\n");
+        let value = serde::de::IgnoredAny::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::ExpectedValue("type header"));
     }
 
     #[test]
-    fn test_enum() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        enum TestEnum {
-            One(usize),
-            Two(usize, String),
-            Three { value: usize },
-            Four,
+    fn test_skip_value() {
+        let input = b"+a\r\n-b\r\n:1\r\n(123\r\n,1.5\r\n#t\r\n_\r\n$1\r\nc\r\n!1\r\nd\r\n\
+            =7\r\ntxt:abc\r\n*2\r\n:1\r\n~1\r\n+x\r\n%1\r\n+k\r\n*0\r\n\
+            |1\r\n+a\r\n:1\r\n*1\r\n>1\r\n:2\r\n\
+            $?\r\n;2\r\nab\r\n;1\r\nc\r\n;0\r\n\
+            *?\r\n:1\r\n*?\r\n.\r\n.\r\n\
+            %?\r\n+k\r\n~?\r\n:1\r\n.\r\n.\r\n:9\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let mut read_d = Deserializer::from_read(&input[..]);
+        for _ in 0..16 {
+            d.skip_value().unwrap();
+            read_d.skip_value().unwrap();
         }
+        assert_eq!(d.read_integer_reply().unwrap(), 9);
+        assert_eq!(read_d.read_integer_reply().unwrap(), 9);
+        d.expect_eof().unwrap();
 
-        test_deserialize(b"%1\r\n+One\r\n:300\r\n", |value: TestEnum| {
-            assert_eq!(value, TestEnum::One(300))
-        });
-        test_deserialize(
-            b"%1\r\n+Two\r\n*2\r\n:300\r\n+teststring\r\n",
-            |value: TestEnum| assert_eq!(value, TestEnum::Two(300, String::from("teststring"))),
+        let mut d = Deserializer::from_slice(b"$?\r\n;2\r\nab\r\n:1\r\n");
+        let err = Error::expected_marker_found("';' for streamed string", b':');
+        assert_eq!(d.skip_value().unwrap_err(), err);
+
+        let mut d = Deserializer::from_slice(b"*?\r\n:1\r\n");
+        assert!(d.skip_value().unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn test_expect_eof() {
+        let input = b":1\r\n+OK\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let _: u64 = Deserialize::deserialize(&mut d).unwrap();
+        let _: String = Deserialize::deserialize(&mut d).unwrap();
+        assert!(d.expect_eof().is_ok());
+
+        let mut d = Deserializer::from_slice(input);
+        let _: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(d.expect_eof().unwrap_err(), Error::TrailingData(Some(5)));
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let _: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(d.expect_eof().unwrap_err(), Error::TrailingData(None));
+        let _: String = Deserialize::deserialize(&mut d).unwrap();
+        assert!(d.expect_eof().is_ok());
+    }
+
+    #[test]
+    fn test_expect_marker() {
+        let input = b"|1\r\n+ttl\r\n:1\r\n*2\r\n+a\r\n:1\r\n%1\r\n+k\r\n+v\r\n";
+        let mut d = Deserializer::from_slice(input);
+        d.expect_marker(markers::ARRAY).unwrap();
+        assert_eq!(d.read_aggregate_len().unwrap(), 2);
+        assert_eq!(String::deserialize(&mut d).unwrap(), "a");
+        assert_eq!(u64::deserialize(&mut d).unwrap(), 1);
+
+        // a mismatch consumes nothing
+        let err = d.expect_marker(markers::ARRAY).unwrap_err();
+        assert_eq!(
+            err,
+            Error::expected_marker_found("'*' for array", markers::MAP)
         );
-        test_deserialize(
-            b"%1\r\n+Three\r\n%1\r\n+value\r\n:300\r\n",
+        assert_eq!(err.to_string(), "expected '*' for array, found '%'");
+        let value: HashMap<String, String> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value["k"], "v");
+        assert_eq!(d.expect_marker(markers::MAP).unwrap_err(), Error::EOF);
+
+        let mut d = Deserializer::from_read(&input[..]);
+        d.expect_marker(markers::ATTRIBUTE).unwrap();
+        assert_eq!(d.read_aggregate_len().unwrap(), 1);
+        let err = d.expect_marker(markers::BLOB_STRING).unwrap_err();
+        assert_eq!(
+            err,
+            Error::expected_marker_found("'$' for blob string", b'+')
+        );
+    }
+
+    #[test]
+    fn test_read_integer_reply() {
+        let input = b":0\r\n:-42\r\n:9223372036854775807\r\n:-9223372036854775808\r\n";
+        let mut d = Deserializer::from_slice(input);
+        assert_eq!(d.read_integer_reply().unwrap(), 0);
+        assert_eq!(d.read_integer_reply().unwrap(), -42);
+        assert_eq!(d.read_integer_reply().unwrap(), i64::MAX);
+        assert_eq!(d.read_integer_reply().unwrap(), i64::MIN);
+        assert_eq!(d.read_integer_reply().unwrap_err(), Error::EOF);
+
+        let mut d = Deserializer::from_read(&input[..]);
+        assert_eq!(d.read_integer_reply().unwrap(), 0);
+        assert_eq!(d.read_integer_reply().unwrap(), -42);
+
+        for input in [
+            &b":9223372036854775808\r\n"[..],
+            b":-9223372036854775809\r\n",
+            b"+1\r\n",
+            b":-\r\n",
+            b":\r\n",
+            b":01\r\n",
+            b":1a\r\n",
+            b":1",
+        ] {
+            let mut d = Deserializer::from_slice(input);
+            assert!(d.read_integer_reply().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_f32() {
+        test_deserialize(b":16777216\r\n", |value: f32| {
+            assert_eq!(value, 16777216.0);
+        });
+
+        // not representable in f32, rounded to nearest even
+        test_deserialize(b":16777217\r\n", |value: f32| {
+            assert_eq!(value, 16777216.0);
+        });
+
+        test_deserialize(b":-3\r\n", |value: f32| {
+            assert_eq!(value, -3.0);
+        });
+
+        // beyond i64, still within f32 range
+        test_deserialize(b":100000000000000000000\r\n", |value: f32| {
+            assert_eq!(value, 1e20);
+        });
+
+        test_deserialize(b",1.5\r\n", |value: f32| {
+            assert_eq!(value, 1.5);
+        });
+        test_deserialize(b",inf\r\n", |value: f32| {
+            assert_eq!(value, f32::INFINITY);
+        });
+        test_deserialize_result(b",1e300\r\n", |value: Result<f32>| {
+            assert_eq!(value, Err(Error::overflow()));
+        });
+        test_deserialize_result(b",-1e300\r\n", |value: Result<f32>| {
+            assert_eq!(value, Err(Error::overflow()));
+        });
+
+        let input = format!(":1{}\r\n", "0".repeat(39));
+        test_deserialize_result(input.as_bytes(), |value: Result<f32>| {
+            assert!(value.is_err());
+        });
+
+        test_deserialize_result(b":1.5\r\n", |value: Result<f32>| {
+            assert!(value.is_err());
+        });
+        test_deserialize_result(b":-\r\n", |value: Result<f32>| {
+            assert!(value.is_err());
+        });
+    }
+
+    #[cfg(feature = "ordered-float")]
+    #[test]
+    fn test_ordered_float() {
+        use std::collections::BTreeMap;
+
+        use ordered_float::OrderedFloat;
+
+        test_deserialize(b",1.5\r\n", |value: OrderedFloat<f64>| {
+            assert_eq!(value, OrderedFloat(1.5));
+        });
+        test_deserialize(b",-inf\r\n", |value: OrderedFloat<f64>| {
+            assert_eq!(value, OrderedFloat(f64::NEG_INFINITY));
+        });
+        test_deserialize(b",nan\r\n", |value: OrderedFloat<f64>| {
+            assert!(value.is_nan());
+            assert_eq!(value, OrderedFloat(f64::NAN));
+        });
+
+        let input = b"%4\r\n,nan\r\n:3\r\n,inf\r\n:2\r\n,-1.5\r\n:0\r\n,2\r\n:1\r\n";
+        test_deserialize(input, |value: BTreeMap<OrderedFloat<f64>, u64>| {
+            let values: Vec<u64> = value.into_values().collect();
+            assert_eq!(values, [0, 1, 2, 3]);
+        });
+
+        let mut map = BTreeMap::new();
+        map.insert(OrderedFloat(2.0), 1);
+        map.insert(OrderedFloat(f64::INFINITY), 2);
+        map.insert(OrderedFloat(-1.5), 0);
+        let buf = crate::to_vec(&map).unwrap();
+        assert_eq!(buf, b"%3\r\n,-1.5\r\n:0\r\n,2\r\n:1\r\n,inf\r\n:2\r\n");
+        test_deserialize(&buf, |value: BTreeMap<OrderedFloat<f64>, u64>| {
+            assert_eq!(value, map);
+        });
+
+        // NaN reads but isn't written
+        map.insert(OrderedFloat(f64::NAN), 3);
+        assert_eq!(crate::to_vec(&map).unwrap_err(), Error::NaN);
+    }
+
+    #[test]
+    fn test_char() {
+        test_deserialize(b"+a\r\n", |value: char| {
+            assert_eq!(value, 'a');
+        });
+    }
+
+    #[test]
+    fn test_seq() {
+        test_deserialize(b"*3\r\n:1\r\n:2\r\n:3\r\n", |value: Vec<u64>| {
+            assert_eq!(value, [1, 2, 3]);
+        });
+
+        test_deserialize(
+            b"*2\r\n*3\r\n:1\r\n$5\r\nhello\r\n:2\r\n#f\r\n",
+            |value: ((u64, String, u64), bool)| {
+                assert_eq!(value, ((1, String::from("hello"), 2), false));
+            },
+        );
+    }
+
+    #[test]
+    fn test_tuple_unread_elements() {
+        let input = b"*3\r\n:1\r\n*1\r\n:2\r\n:3\r\n+next\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let value: (u64, Vec<u64>) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, (1, vec![2]));
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: (u64,) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, (1,));
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        let mut d = Deserializer::from_slice(input).deny_unread_elements(true);
+        let value: Result<(u64, Vec<u64>)> = Deserialize::deserialize(&mut d);
+        assert_eq!(
+            value.unwrap_err(),
+            Error::UnexpectedValue("unread elements")
+        );
+
+        test_deserialize(b"*3\r\n:1\r\n_\r\n~1\r\n_\r\n", |value: (u64,)| {
+            assert_eq!(value, (1,));
+        });
+    }
+
+    #[test]
+    fn test_tuple_prefix_of_longer_array() {
+        // trailing metadata of any type is skipped, the stream stays in sync
+        let input = b"*5\r\n$3\r\nkey\r\n:10\r\n%1\r\n+ttl\r\n:5\r\n|1\r\n+a\r\n:1\r\n,1.5\r\n\
+            *2\r\n#t\r\n_\r\n:42\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let value: (&str, i64) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, ("key", 10));
+        assert_eq!(u64::deserialize(&mut d).unwrap(), 42);
+        d.expect_eof().unwrap();
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: (String, i64) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, ("key".into(), 10));
+        assert_eq!(u64::deserialize(&mut d).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_tuple_optional_trailing() {
+        test_deserialize(b"*2\r\n:1\r\n:2\r\n", |value: (u64, u64, Option<u64>)| {
+            assert_eq!(value, (1, 2, None));
+        });
+        test_deserialize(
+            b"*3\r\n:1\r\n:2\r\n:3\r\n",
+            |value: (u64, u64, Option<u64>)| {
+                assert_eq!(value, (1, 2, Some(3)));
+            },
+        );
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Reply(u64, Option<String>);
+        test_deserialize(b"*1\r\n:1\r\n", |value: Reply| {
+            assert_eq!(value, Reply(1, None));
+        });
+
+        test_deserialize_result(b"*1\r\n:1\r\n", |value: Result<(u64, u64)>| {
+            assert!(value.is_err());
+        });
+
+        // streamed arrays end early or have elements left over
+        test_deserialize(
+            b"*?\r\n:1\r\n:2\r\n.\r\n",
+            |value: (u64, u64, Option<u64>)| {
+                assert_eq!(value, (1, 2, None));
+            },
+        );
+        test_deserialize(b"*?\r\n:1\r\n:2\r\n:3\r\n.\r\n", |value: (u64, u64)| {
+            assert_eq!(value, (1, 2));
+        });
+    }
+
+    #[test]
+    fn test_map_non_string_keys() {
+        let input = b"%2\r\n:1\r\n+v\r\n:-2\r\n$1\r\nw\r\n";
+        test_deserialize(input, |value: HashMap<i64, String>| {
+            assert_eq!(value, HashMap::from([(1, "v".into()), (-2, "w".into())]));
+        });
+        test_deserialize(b"%1\r\n$1\r\nk\r\n:1\r\n", |value: HashMap<String, u64>| {
+            assert_eq!(value, HashMap::from([("k".into(), 1)]));
+        });
+        test_deserialize(b"%1\r\n#t\r\n:1\r\n", |value: HashMap<bool, u64>| {
+            assert_eq!(value, HashMap::from([(true, 1)]));
+        });
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Reply {
+            k: u64,
+        }
+        test_deserialize(b"%1\r\n$1\r\nk\r\n:1\r\n", |value: Reply| {
+            assert_eq!(value, Reply { k: 1 });
+        });
+        test_deserialize_result(b"%1\r\n:0\r\n:1\r\n", |value: Result<Reply>| {
+            let err = Error::expected_marker_found("'+' or '$' for identifier", b':');
+            assert_eq!(value.unwrap_err(), err);
+        });
+        test_deserialize_result(b"%1\r\n*1\r\n+k\r\n:1\r\n", |value: Result<Reply>| {
+            let err = Error::expected_marker_found("'+' or '$' for identifier", b'*');
+            assert_eq!(value.unwrap_err(), err);
+        });
+        let mut d = Deserializer::from_slice(b"%1\r\n:0\r\n:1\r\n").assume_ordered_map(true);
+        let value: Result<Reply> = Deserialize::deserialize(&mut d);
+        let err = Error::expected_marker_found("'+' or '$' for identifier", b':');
+        assert_eq!(value.unwrap_err(), err);
+    }
+
+    #[test]
+    fn test_struct_flatten() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Inner {
+            b: String,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Reply {
+            a: u64,
+            #[serde(flatten)]
+            inner: Inner,
+            #[serde(flatten)]
+            extra: HashMap<String, Value>,
+        }
+        let input = b"%4\r\n+a\r\n:1\r\n+b\r\n$2\r\nhi\r\n+c\r\n*2\r\n_\r\n,1.5\r\n+d\r\n#t\r\n";
+        test_deserialize(input, |value: Reply| {
+            assert_eq!(value.a, 1);
+            assert_eq!(value.inner, Inner { b: "hi".into() });
+            assert_eq!(value.extra.len(), 2);
+            assert_eq!(
+                value.extra["c"],
+                Value::Array(vec![Value::Null, Value::Double(1.5)])
+            );
+            assert_eq!(value.extra["d"], Value::Boolean(true));
+        });
+    }
+
+    #[test]
+    fn test_map() {
+        test_deserialize(
+            b"%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n",
+            |value: HashMap<String, usize>| {
+                let kv = value.into_iter().collect::<Vec<_>>();
+                assert!(kv.contains(&("first".to_string(), 1)));
+                assert!(kv.contains(&("second".to_string(), 2)));
+            },
+        );
+
+        #[derive(PartialEq, Deserialize, Debug)]
+        struct CustomMap {
+            first: usize,
+            second: f64,
+        }
+        test_deserialize(
+            b"%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n",
+            |value: CustomMap| {
+                assert_eq!(
+                    value,
+                    CustomMap {
+                        first: 1,
+                        second: 2.0
+                    }
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_streamed_map() {
+        let input = b"%?\r\n+first\r\n:1\r\n+second\r\n:2\r\n.\r\n+next\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let value: HashMap<String, usize> = Deserialize::deserialize(&mut d).unwrap();
+        let expected = HashMap::from([("first".into(), 1), ("second".into(), 2)]);
+        assert_eq!(value, expected);
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        #[derive(PartialEq, Deserialize, Debug)]
+        struct CustomMap {
+            second: usize,
+        }
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: CustomMap = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, CustomMap { second: 2 });
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        test_deserialize(b"%?\r\n.\r\n", |value: HashMap<String, usize>| {
+            assert!(value.is_empty());
+        });
+
+        let mut d = Deserializer::from_slice(b"%?\r\n+first\r\n:1\r\n");
+        let value: Result<HashMap<String, usize>> = Deserialize::deserialize(&mut d);
+        assert!(value.unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn test_bytes_seq() {
+        test_deserialize(b"*3\r\n:1\r\n:2\r\n:3\r\n", |value: Vec<u8>| {
+            assert_eq!(value, [1, 2, 3]);
+        });
+        test_deserialize(
+            b"*3\r\n:1\r\n:2\r\n:3\r\n",
+            |value: serde_bytes::ByteBuf| {
+                assert_eq!(value, [1, 2, 3]);
+            },
+        );
+        test_deserialize(b"$3\r\n\x01\x02\x03\r\n", |value: serde_bytes::ByteBuf| {
+            assert_eq!(value, [1, 2, 3]);
+        });
+        test_deserialize(b"$0\r\n\r\n", |value: serde_bytes::ByteBuf| {
+            assert!(value.is_empty());
+        });
+
+        // a blob is not a sequence of its bytes for other element types
+        let value: Result<Vec<u64>> = from_slice(b"$2\r\nab\r\n");
+        assert!(value.is_err());
+        let value: Result<Vec<u8>> = from_slice(b"$2\r\nab\r\n");
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_map_duplicate_keys() {
+        test_deserialize(
+            b"%3\r\n+b\r\n:1\r\n+a\r\n:2\r\n+c\r\n:3\r\n",
+            |value: BTreeMap<String, u64>| {
+                let kv = value.into_iter().collect::<Vec<_>>();
+                assert_eq!(kv, [("a".into(), 2), ("b".into(), 1), ("c".into(), 3)]);
+            },
+        );
+
+        let input = b"%2\r\n+a\r\n:1\r\n+a\r\n:2\r\n";
+        test_deserialize(input, |value: BTreeMap<String, u64>| {
+            assert_eq!(value, BTreeMap::from([("a".to_owned(), 2)]));
+        });
+        test_deserialize(input, |value: HashMap<String, u64>| {
+            assert_eq!(value, HashMap::from([("a".to_owned(), 2)]));
+        });
+        test_deserialize(input, |value: Value| {
+            assert_eq!(
+                value,
+                Value::Map(vec![
+                    (Value::SimpleString("a".into()), Value::Integer(1)),
+                    (Value::SimpleString("a".into()), Value::Integer(2)),
+                ])
+            );
+        });
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct A {
+            a: u64,
+        }
+        let value: Result<A> = crate::from_slice(input);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_map_unread_entries() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct A {
+            a: u64,
+        }
+
+        let input = b"%2\r\n+a\r\n:1\r\n+extra\r\n*2\r\n:2\r\n:3\r\n+next\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let value: A = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, A { a: 1 });
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        // visitor reading only the first key
+        #[derive(Debug)]
+        struct FirstKey(String);
+        struct FirstKeyVisitor;
+        impl<'de> serde::de::Visitor<'de> for FirstKeyVisitor {
+            type Value = FirstKey;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "map")
+            }
+
+            fn visit_map<M: serde::de::MapAccess<'de>>(
+                self,
+                mut map: M,
+            ) -> std::result::Result<FirstKey, M::Error> {
+                let key = map.next_key()?.unwrap_or_default();
+                Ok(FirstKey(key))
+            }
+        }
+        impl<'de> Deserialize<'de> for FirstKey {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                d: D,
+            ) -> std::result::Result<Self, D::Error> {
+                d.deserialize_map(FirstKeyVisitor)
+            }
+        }
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: FirstKey = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value.0, "a");
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+        assert!(d.expect_eof().is_ok());
+
+        let mut d = Deserializer::from_slice(input).deny_unread_elements(true);
+        let value: Result<FirstKey> = Deserialize::deserialize(&mut d);
+        assert_eq!(
+            value.unwrap_err(),
+            Error::UnexpectedValue("unread elements")
+        );
+    }
+
+    #[test]
+    fn test_map_borrowed_keys() {
+        use std::borrow::Cow;
+
+        use crate::Value;
+
+        // plain `Cow<str>` always deserializes owned, `#[serde(borrow)]` opts in
+        #[derive(PartialEq, Eq, Hash, Deserialize, Debug)]
+        struct Key<'a>(#[serde(borrow)] Cow<'a, str>);
+
+        let input = b"%2\r\n+a\r\n:1\r\n$1\r\nb\r\n+x\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let value: HashMap<Key, Value> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value.len(), 2);
+        assert!(value.keys().all(|k| matches!(k.0, Cow::Borrowed(_))));
+        assert_eq!(value[&Key("a".into())], Value::Integer(1));
+        assert_eq!(value[&Key("b".into())], Value::SimpleString("x".into()));
+
+        let mut d = Deserializer::from_slice(input);
+        let value: HashMap<&str, Value> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value["a"], Value::Integer(1));
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: HashMap<Key, Value> = Deserialize::deserialize(&mut d).unwrap();
+        assert!(value.keys().all(|k| matches!(k.0, Cow::Owned(_))));
+    }
+
+    #[test]
+    fn test_cow_str_by_source() {
+        use std::borrow::Cow;
+
+        // `#[serde(borrow)]` is needed for serde to borrow into a `Cow` field
+        #[derive(Deserialize)]
+        struct Reply<'a> {
+            #[serde(borrow)]
+            name: Cow<'a, str>,
+            #[serde(borrow)]
+            role: Cow<'a, str>,
+        }
+
+        let input = b"%2\r\n+name\r\n$5\r\nalice\r\n+role\r\n+admin\r\n";
+        let value: Reply = from_slice(input).unwrap();
+        assert!(matches!(value.name, Cow::Borrowed("alice")));
+        assert!(matches!(value.role, Cow::Borrowed("admin")));
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let value = Reply::deserialize(&mut d).unwrap();
+        assert!(matches!(value.name, Cow::Owned(ref s) if s == "alice"));
+        assert!(matches!(value.role, Cow::Owned(ref s) if s == "admin"));
+
+        // otherwise serde always fills `Cow` owned, even from a slice
+        let value: Cow<str> = from_slice(b"+alice\r\n").unwrap();
+        assert!(matches!(value, Cow::Owned(_)));
+        let value: Vec<Cow<str>> = from_slice(b"*1\r\n+alice\r\n").unwrap();
+        assert!(matches!(value[0], Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_map_borrowed_str_keys() {
+        let input = b"%2\r\n+a\r\n:1\r\n$2\r\nbc\r\n:2\r\n";
+        let value: HashMap<&str, i64> = from_slice(input).unwrap();
+        assert_eq!(value, HashMap::from([("a", 1), ("bc", 2)]));
+        let input_range = input.as_ptr_range();
+        assert!(value.keys().all(|k| input_range.contains(&k.as_ptr())));
+
+        // nothing to borrow from when reading from io, keys must be owned
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: Result<HashMap<&str, i64>> = Deserialize::deserialize(&mut d);
+        assert!(matches!(value, Err(Error::Custom(_))), "{:?}", value);
+
+        let mut d = Deserializer::from_read(&input[..]);
+        let value: HashMap<String, i64> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value["bc"], 2);
+    }
+
+    #[test]
+    fn test_from_slice_result() {
+        use crate::types::borrowed::SimpleError;
+
+        let value = from_slice_result::<_, (usize, &str)>(b"*2\r\n:1\r\n+a\r\n").unwrap();
+        assert_eq!(value, Ok((1, "a")));
+
+        let value = from_slice_result::<_, usize>(b"-ERR oops\r\n").unwrap();
+        assert_eq!(value, Err(SimpleError::from("ERR oops")));
+
+        let value = from_slice_result::<_, usize>(b"!9\r\nERR\r\noops\r\n").unwrap();
+        assert_eq!(value, Err(SimpleError::from("ERR\r\noops")));
+
+        let value = from_slice_result::<_, usize>(b"|1\r\n+a\r\n+b\r\n-ERR\r\n").unwrap();
+        assert_eq!(value, Err(SimpleError::from("ERR")));
+
+        let value = from_slice_result::<_, usize>(b"+abc\r\n");
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_incomplete() {
+        // yields the bytes then would block
+        struct NonBlocking(&'static [u8]);
+        impl Read for NonBlocking {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                self.0.read(buf)
+            }
+        }
+
+        let mut d = Deserializer::from_read(NonBlocking(b"*2\r\n:1\r\n"));
+        let err = <(u64, u64)>::deserialize(&mut d).unwrap_err();
+        assert_eq!(err, Error::Incomplete);
+        assert!(err.needs_more_data());
+
+        let err = from_read::<_, u64>(io::repeat(b'x')).unwrap_err();
+        assert!(!err.needs_more_data());
+
+        // retry contract: accumulate and parse from the start of the buffer
+        let mut buf = b"*2\r\n:1\r\n:".to_vec();
+        let err = from_slice::<_, (u64, u64)>(&buf).unwrap_err();
+        assert!(err.needs_more_data());
+        buf.extend_from_slice(b"2\r\n+next");
+        let mut d = Deserializer::from_slice(&buf);
+        let value: (u64, u64) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, (1, 2));
+        assert_eq!(d.get_consumed_bytes(), 12);
+
+        // any truncation of a frame asks for more data
+        let input = b"*6\r\n:-12\r\n+OK\r\n,-1.5\r\n#t\r\n$3\r\nabc\r\n%1\r\n+a\r\n_\r\n";
+        for end in 0..input.len() {
+            let err = from_slice::<_, Value>(&input[..end]).unwrap_err();
+            assert!(err.needs_more_data(), "{}: {:?}", end, err);
+        }
+        assert!(from_slice::<_, Value>(&input[..]).is_ok());
+    }
+
+    #[test]
+    fn test_consumed_bytes() {
+        let input = b"+OK\r\n$3\r\nabc\r\n*2\r\n:1\r\n#t\r\n|1\r\n+k\r\n:1\r\n:7\r\n";
+        let mut d = Deserializer::from_slice(input);
+        assert_eq!(d.get_consumed_bytes(), 0);
+        assert_eq!(<&str>::deserialize(&mut d).unwrap(), "OK");
+        assert_eq!(d.get_consumed_bytes(), 5);
+        assert_eq!(<&str>::deserialize(&mut d).unwrap(), "abc");
+        assert_eq!(d.get_consumed_bytes(), 14);
+        assert_eq!(d.peek_type().unwrap(), RespType::Array);
+        assert_eq!(d.get_consumed_bytes(), 14);
+        assert_eq!(<(u64, bool)>::deserialize(&mut d).unwrap(), (1, true));
+        assert_eq!(d.get_consumed_bytes(), 26);
+        // the skipped attribute counts as consumed
+        assert_eq!(u64::deserialize(&mut d).unwrap(), 7);
+        assert_eq!(d.get_consumed_bytes(), input.len());
+
+        let mut d = Deserializer::from_slice(b"+a\n:1\n").lenient_line_endings(true);
+        assert_eq!(<&str>::deserialize(&mut d).unwrap(), "a");
+        assert_eq!(d.get_consumed_bytes(), 3);
+        assert_eq!(u64::deserialize(&mut d).unwrap(), 1);
+        assert_eq!(d.get_consumed_bytes(), 6);
+    }
+
+    #[test]
+    fn test_ordered_map() {
+        #[derive(PartialEq, Deserialize, Debug)]
+        struct Ordered {
+            a: usize,
+            b: usize,
+        }
+
+        let input = b"%2\r\n+a\r\n:1\r\n+b\r\n:2\r\n";
+        let mut d = Deserializer::from_slice(input).assume_ordered_map(true);
+        let value: Ordered = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Ordered { a: 1, b: 2 });
+
+        let mut d = Deserializer::from_read(&input[..]).assume_ordered_map(true);
+        let value: Ordered = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Ordered { a: 1, b: 2 });
+
+        // fallback to key matching
+        let input = b"%2\r\n$1\r\nb\r\n:2\r\n+a\r\n:1\r\n";
+        let mut d = Deserializer::from_slice(input).assume_ordered_map(true);
+        let value: Ordered = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Ordered { a: 1, b: 2 });
+
+        let mut d = Deserializer::from_read(&input[..]).assume_ordered_map(true);
+        let value: Ordered = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Ordered { a: 1, b: 2 });
+
+        // streamed map
+        let input = b"%?\r\n+a\r\n:1\r\n+b\r\n:2\r\n.\r\n";
+        let mut d = Deserializer::from_slice(input).assume_ordered_map(true);
+        let value: Ordered = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Ordered { a: 1, b: 2 });
+
+        let mut d = Deserializer::from_read(&input[..]).assume_ordered_map(true);
+        let value: Ordered = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Ordered { a: 1, b: 2 });
+    }
+
+    #[test]
+    fn test_ordered_map_positional() {
+        // field identifier accepting only positional index
+        struct Field(u64);
+        struct FieldVisitor;
+        impl<'de> serde::de::Visitor<'de> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "field index")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Field(v))
+            }
+        }
+        impl<'de> Deserialize<'de> for Field {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer.deserialize_identifier(FieldVisitor)
+            }
+        }
+        struct Positional(Vec<(u64, usize)>);
+        struct PositionalVisitor;
+        impl<'de> serde::de::Visitor<'de> for PositionalVisitor {
+            type Value = Positional;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "map")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut fields = Vec::new();
+                while let Some(Field(idx)) = map.next_key()? {
+                    fields.push((idx, map.next_value()?));
+                }
+                Ok(Positional(fields))
+            }
+        }
+        impl<'de> Deserialize<'de> for Positional {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer.deserialize_struct("Positional", &["a", "b"], PositionalVisitor)
+            }
+        }
+
+        let input = b"%2\r\n+a\r\n:1\r\n+b\r\n:2\r\n";
+        let mut d = Deserializer::from_slice(input).assume_ordered_map(true);
+        let value: Positional = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value.0, [(0, 1), (1, 2)]);
+
+        let mut d = Deserializer::from_slice(input);
+        let value: Result<Positional> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_ordered_map_unread_fields() {
+        // visitor reading the first field only, and its value if `value`
+        struct First<const VALUE: bool>(Option<u64>);
+        struct FirstVisitor<const VALUE: bool>;
+        impl<'de, const VALUE: bool> serde::de::Visitor<'de> for FirstVisitor<VALUE> {
+            type Value = First<VALUE>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "map")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                map.next_key::<serde::de::IgnoredAny>()?;
+                if VALUE {
+                    return Ok(First(Some(map.next_value()?)));
+                }
+                Ok(First(None))
+            }
+        }
+        impl<'de, const VALUE: bool> Deserialize<'de> for First<VALUE> {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer.deserialize_struct("First", &["a", "b"], FirstVisitor)
+            }
+        }
+
+        let input = b"%2\r\n+a\r\n:1\r\n+b\r\n*1\r\n:2\r\n+next\r\n";
+        let mut d = Deserializer::from_slice(input).assume_ordered_map(true);
+        let value: First<true> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value.0, Some(1));
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        let mut d = Deserializer::from_read(&input[..]).assume_ordered_map(true);
+        let value: First<false> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value.0, None);
+        let next: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(next, "next");
+
+        let mut d = Deserializer::from_slice(input)
+            .assume_ordered_map(true)
+            .deny_unread_elements(true);
+        let value: Result<First<true>> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.err(), Some(Error::UnexpectedValue("unread elements")));
+    }
+
+    #[test]
+    fn test_max_length() {
+        let input = b"$999999999999\r\nhello\r\n";
+        let mut d = Deserializer::from_slice(input).with_max_length(1024);
+        let value: Result<String> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+
+        let mut d = Deserializer::from_read(&input[..]).with_max_length(1024);
+        let value: Result<String> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+
+        let input = b"*3\r\n:1\r\n:2\r\n:3\r\n";
+        let mut d = Deserializer::from_slice(input).with_max_length(2);
+        let value: Result<Vec<u64>> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+        let mut d = Deserializer::from_slice(input).with_max_length(3);
+        let value: Vec<u64> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, [1, 2, 3]);
+
+        let input = b"%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n";
+        let mut d = Deserializer::from_slice(input).with_max_length(1);
+        let value: Result<HashMap<String, usize>> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+    }
+
+    #[test]
+    fn test_max_line_len() {
+        let mut input = b"+".to_vec();
+        input.extend_from_slice(&[b'a'; 10_000]);
+        let mut d = Deserializer::from_read(&input[..]).with_max_line_len(1024);
+        let value: Result<String> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::LengthLimitExceeded);
+
+        let mut d = Deserializer::from_read(&b"+hello\r\n"[..]).with_max_line_len(5);
+        let value: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, "hello");
+    }
+
+    #[test]
+    fn test_lenient_line_endings() {
+        let input = b"+abc\n$3\nxyz\n";
+
+        let mut d = Deserializer::from_slice(input);
+        let value: Result<String> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+
+        let mut d = Deserializer::from_slice(input).lenient_line_endings(true);
+        let value: (String, String) = (
+            Deserialize::deserialize(&mut d).unwrap(),
+            Deserialize::deserialize(&mut d).unwrap(),
+        );
+        assert_eq!(value, ("abc".into(), "xyz".into()));
+        d.expect_eof().unwrap();
+
+        let mut d = Deserializer::from_read(&input[..]).lenient_line_endings(true);
+        let value: (String, String) = (
+            Deserialize::deserialize(&mut d).unwrap(),
+            Deserialize::deserialize(&mut d).unwrap(),
+        );
+        assert_eq!(value, ("abc".into(), "xyz".into()));
+        d.expect_eof().unwrap();
+
+        // `\r\n` is still accepted, a lone `\r` is not
+        let mut d = Deserializer::from_slice(b"+abc\r\n").lenient_line_endings(true);
+        let value: String = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, "abc");
+
+        let mut d = Deserializer::from_slice(b"+abc\rd\n").lenient_line_endings(true);
+        let value: Result<String> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_lenient_line_endings_types() {
+        fn lenient<'de, T: Deserialize<'de>>(input: &'de [u8]) -> Result<T> {
+            let mut d = Deserializer::from_slice(input).lenient_line_endings(true);
+            let value = T::deserialize(&mut d)?;
+            d.expect_eof()?;
+            let mut d = Deserializer::from_read(input).lenient_line_endings(true);
+            Value::deserialize(&mut d)?;
+            d.expect_eof()?;
+            Ok(value)
+        }
+
+        assert_eq!(lenient::<String>(b"+ok\n").unwrap(), "ok");
+        assert_eq!(lenient::<i64>(b":5\n").unwrap(), 5);
+        assert_eq!(lenient::<i64>(b":-5\n").unwrap(), -5);
+        assert_eq!(lenient::<f64>(b",1.5\n").unwrap(), 1.5);
+        assert_eq!(lenient::<f64>(b",inf\n").unwrap(), f64::INFINITY);
+        assert!(lenient::<bool>(b"#t\n").unwrap());
+        assert_eq!(lenient::<Option<i64>>(b"_\n").unwrap(), None);
+        assert_eq!(lenient::<Vec<i64>>(b"*2\n:1\n:2\n").unwrap(), [1, 2]);
+        let map: HashMap<String, i64> = lenient(b"%1\n+a\n:1\n").unwrap();
+        assert_eq!(map["a"], 1);
+
+        // mixed endings within a frame are fine
+        assert_eq!(lenient::<Vec<i64>>(b"*2\r\n:1\n:2\r\n").unwrap(), [1, 2]);
+
+        let mut d = Deserializer::from_slice(b":5\n");
+        let value: Result<i64> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_attribute_in_array() {
+        let input = b"*2\r\n|1\r\n+a\r\n+b\r\n:1\r\n:2\r\n";
+        test_deserialize(input, |value: Vec<u64>| {
+            assert_eq!(value, [1, 2]);
+        });
+        test_deserialize(input, |value: (u64, u64)| {
+            assert_eq!(value, (1, 2));
+        });
+        test_deserialize(
+            b"*2\r\n:1\r\n|1\r\n+a\r\n*0\r\n:2\r\n",
+            |value: (u64, u64)| {
+                assert_eq!(value, (1, 2));
+            },
+        );
+
+        // attribute prefixed elements left unread are skipped along with it
+        let mut d = Deserializer::from_slice(b"*2\r\n:1\r\n|1\r\n+a\r\n+b\r\n:2\r\n:3\r\n");
+        let value: (u64,) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, (1,));
+        let value: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 3);
+
+        // attribute doesn't count as an element
+        let mut d = Deserializer::from_slice(input).deny_unread_elements(true);
+        let value: Result<(u64,)> = Deserialize::deserialize(&mut d);
+        assert_eq!(
+            value.unwrap_err(),
+            Error::UnexpectedValue("unread elements")
+        );
+    }
+
+    #[test]
+    fn test_attribute_in_map() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Reply {
+            b: u64,
+        }
+        // attributes on a key and on a value, followed by another reply
+        let input = b"%2\r\n+a\r\n|1\r\n+t\r\n:1\r\n:1\r\n|1\r\n+t\r\n:2\r\n+b\r\n:2\r\n:3\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let value: HashMap<String, u64> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(
+            value,
+            HashMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)])
+        );
+        let value: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 3);
+
+        // the attribute prefixed pair is skipped as one pair
+        let mut d = Deserializer::from_slice(input);
+        let value: Reply = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, Reply { b: 2 });
+        let value: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 3);
+    }
+
+    #[test]
+    fn test_collect_attributes() {
+        let attr: &[u8] =
+            b"|1\r\n+key-popularity\r\n%2\r\n$1\r\na\r\n,0.1923\r\n$1\r\nb\r\n,0.0012\r\n";
+        let input = [attr, b"*2\r\n:2039123\r\n|1\r\n+ttl\r\n:10\r\n:9543892\r\n"].concat();
+        let mut d = Deserializer::from_slice(&input).collect_attributes(true);
+        let value: (u64, u64) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, (2039123, 9543892));
+        assert_eq!(d.take_attributes(), [attr, b"|1\r\n+ttl\r\n:10\r\n"]);
+        assert!(d.take_attributes().is_empty());
+
+        let mut d = Deserializer::from_read(&input[..]).collect_attributes(true);
+        let _: (u64, u64) = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(d.take_attributes(), [attr, b"|1\r\n+ttl\r\n:10\r\n"]);
+
+        // kept byte for byte, not re-encoded
+        let scored = b"|1\r\n+score\r\n,1.50\r\n:7\r\n";
+        let mut d = Deserializer::from_read(&scored[..]).collect_attributes(true);
+        let value: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(d.take_attributes(), [b"|1\r\n+score\r\n,1.50\r\n"]);
+
+        // not collected by default
+        let mut d = Deserializer::from_slice(&input);
+        let _: (u64, u64) = Deserialize::deserialize(&mut d).unwrap();
+        assert!(d.take_attributes().is_empty());
+    }
+
+    #[test]
+    fn test_any_bool() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Any {
+            Text(String),
+            Bool(bool),
+        }
+
+        test_deserialize(b"#t\r\n", |value: Any| {
+            assert_eq!(value, Any::Bool(true));
+        });
+        test_deserialize(b"|1\r\n+a\r\n+b\r\n#f\r\n", |value: Any| {
+            assert_eq!(value, Any::Bool(false));
+        });
+        test_deserialize(b"+t\r\n", |value: Any| {
+            assert_eq!(value, Any::Text("t".into()));
+        });
+    }
+
+    #[test]
+    fn test_set_skip_attribute() {
+        let input = b"|1\r\n+a\r\n+b\r\n:1\r\n";
+        let mut d = Deserializer::from_slice(input);
+        assert!(d.skip_attribute());
+        d.set_skip_attribute(false);
+        assert!(!d.skip_attribute());
+        let value: Result<u64> = Deserialize::deserialize(&mut d);
+        let err = Error::expected_marker_found("':' for unsigned integer", b'|');
+        assert_eq!(value.unwrap_err(), err);
+
+        // WithAttribute restores the setting
+        let mut d = Deserializer::from_slice(b"|1\r\n+a\r\n+b\r\n:1\r\n|1\r\n+a\r\n+b\r\n:2\r\n");
+        let value: WithAttribute<HashMap<String, String>, u64> =
+            Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value.into_inner().1, 1);
+        assert!(d.skip_attribute());
+        let value: u64 = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, 2);
+    }
+
+    #[test]
+    fn test_nested_aggregates() {
+        let input = b"*2\r\n*2\r\n:1\r\n:-2\r\n*2\r\n:3\r\n:4\r\n";
+        test_deserialize(input, |value: Vec<Vec<i64>>| {
+            assert_eq!(value, [[1, -2], [3, 4]]);
+        });
+        test_deserialize(b"*2\r\n*0\r\n*1\r\n:1\r\n", |value: Vec<Vec<i64>>| {
+            assert_eq!(value, [vec![], vec![1]]);
+        });
+
+        let input = b"*2\r\n%2\r\n+a\r\n:1\r\n+b\r\n:2\r\n%1\r\n+c\r\n:-3\r\n";
+        test_deserialize(input, |value: Vec<HashMap<String, i64>>| {
+            assert_eq!(value.len(), 2);
+            assert_eq!(value[0], HashMap::from([("a".into(), 1), ("b".into(), 2)]));
+            assert_eq!(value[1], HashMap::from([("c".into(), -3)]));
+        });
+
+        let input = b"%2\r\n+a\r\n*2\r\n:1\r\n:2\r\n+b\r\n*0\r\n";
+        test_deserialize(input, |value: HashMap<String, Vec<i64>>| {
+            assert_eq!(
+                value,
+                HashMap::from([("a".into(), vec![1, 2]), ("b".into(), vec![])])
+            );
+        });
+
+        // each nesting level takes one depth, siblings don't add up
+        let input = b"*2\r\n*2\r\n:1\r\n:-2\r\n*2\r\n:3\r\n:4\r\n";
+        let mut d = Deserializer::from_slice(input).with_max_depth(2);
+        let value: Vec<Vec<i64>> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, [[1, -2], [3, 4]]);
+        let mut d = Deserializer::from_slice(input).with_max_depth(1);
+        let value: Result<Vec<Vec<i64>>> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::DepthLimitExceeded);
+
+        let input = b"%1\r\n+a\r\n*1\r\n:1\r\n";
+        let mut d = Deserializer::from_read(&input[..]).with_max_depth(2);
+        let value: HashMap<String, Vec<i64>> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value["a"], [1]);
+        let mut d = Deserializer::from_read(&input[..]).with_max_depth(1);
+        let value: Result<HashMap<String, Vec<i64>>> = Deserialize::deserialize(&mut d);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut input = b"*1\r\n".repeat(100_000);
+        input.extend_from_slice(b":1\r\n");
+        let mut d = Deserializer::from_slice(&input).with_max_depth(64);
+        let value: Result<AnySkip> = Deserialize::deserialize(&mut d);
+        assert!(matches!(value, Err(Error::DepthLimitExceeded)));
+
+        let input = b"*1\r\n%1\r\n+a\r\n*1\r\n:1\r\n";
+        let mut d = Deserializer::from_slice(input).with_max_depth(2);
+        let value: Result<Vec<HashMap<String, Vec<u64>>>> = Deserialize::deserialize(&mut d);
+        assert_eq!(value.unwrap_err(), Error::DepthLimitExceeded);
+        let mut d = Deserializer::from_slice(input).with_max_depth(3);
+        let value: Vec<HashMap<String, Vec<u64>>> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value[0]["a"], [1]);
+
+        // depth is restored after leaving an aggregate
+        let input = b"*2\r\n*1\r\n:1\r\n*1\r\n:2\r\n";
+        let mut d = Deserializer::from_slice(input).with_max_depth(2);
+        let value: Vec<Vec<u64>> = Deserialize::deserialize(&mut d).unwrap();
+        assert_eq!(value, [[1], [2]]);
+    }
+
+    #[test]
+    fn test_from_slice_checked() {
+        let mut input = b"*1\r\n".repeat(1_000_000);
+        input.extend_from_slice(b":1\r\n");
+        let err = from_slice_checked::<_, Value>(&input).unwrap_err();
+        assert_eq!(err, Error::DepthLimitExceeded);
+
+        let err = from_slice_checked::<_, Vec<u64>>(b"*1000\r\n:1\r\n").unwrap_err();
+        assert_eq!(err, Error::length_limit_exceeded());
+        let value: HashMap<&str, u64> = from_slice_checked(b"%1\r\n+a\r\n:1\r\n").unwrap();
+        assert_eq!(value["a"], 1);
+
+        // pair counts near usize::MAX used to overflow when doubled
+        let input = b"|9223372036854775808\r\n+a\r\n";
+        let value = from_slice::<_, crate::types::WithRawAttribute<u64>>(input);
+        assert!(value.unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn test_enum() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum TestEnum {
+            One(usize),
+            Two(usize, String),
+            Three { value: usize },
+            Four,
+        }
+
+        test_deserialize(b"%1\r\n+One\r\n:300\r\n", |value: TestEnum| {
+            assert_eq!(value, TestEnum::One(300))
+        });
+        test_deserialize(
+            b"%1\r\n+Two\r\n*2\r\n:300\r\n+teststring\r\n",
+            |value: TestEnum| assert_eq!(value, TestEnum::Two(300, String::from("teststring"))),
+        );
+        test_deserialize(
+            b"%1\r\n+Three\r\n%1\r\n+value\r\n:300\r\n",
             |value: TestEnum| assert_eq!(value, TestEnum::Three { value: 300 }),
         );
         test_deserialize(b"%1\r\n+Four\r\n_\r\n", |value: TestEnum| {
@@ -1481,4 +4699,132 @@ mod tests {
             assert_eq!(value, TestEnum::Four)
         });
     }
+
+    #[test]
+    fn test_enum_variant_tag_encodings() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum TestEnum {
+            One(usize),
+            Two(usize, String),
+            Three { value: usize },
+            Four,
+        }
+
+        let cases: [(&str, &[u8], TestEnum); 4] = [
+            ("One", b":1\r\n", TestEnum::One(1)),
+            ("Two", b"*2\r\n:1\r\n+a\r\n", TestEnum::Two(1, "a".into())),
+            (
+                "Three",
+                b"%1\r\n+value\r\n:1\r\n",
+                TestEnum::Three { value: 1 },
+            ),
+            ("Four", b"_\r\n", TestEnum::Four),
+        ];
+        for (name, body, expected) in cases {
+            let simple = format!("+{}\r\n", name).into_bytes();
+            let blob = format!("${}\r\n{}\r\n", name.len(), name).into_bytes();
+            for tag in [simple, blob] {
+                let input = [b"%1\r\n", &tag[..], body].concat();
+                test_deserialize(&input, |value: TestEnum| assert_eq!(value, expected));
+            }
+        }
+
+        // bare unit variant
+        test_deserialize(b"+Four\r\n", |value: TestEnum| {
+            assert_eq!(value, TestEnum::Four)
+        });
+        test_deserialize(b"$4\r\nFour\r\n", |value: TestEnum| {
+            assert_eq!(value, TestEnum::Four)
+        });
+
+        // tagged array
+        test_deserialize(b"*2\r\n+One\r\n:1\r\n", |value: TestEnum| {
+            assert_eq!(value, TestEnum::One(1))
+        });
+        test_deserialize(b"*2\r\n$3\r\nOne\r\n:1\r\n", |value: TestEnum| {
+            assert_eq!(value, TestEnum::One(1))
+        });
+    }
+
+    #[test]
+    fn test_enum_tagged_array() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum PushEvent {
+            Message {
+                channel: String,
+                payload: String,
+            },
+            PMessage {
+                pattern: String,
+                channel: String,
+                payload: Option<String>,
+            },
+            Subscribe(String, u64),
+            Invalidate(Option<Vec<String>>),
+            Ping,
+        }
+
+        test_deserialize(
+            b">4\r\n+pmessage\r\n+pat\r\n+ch\r\n$5\r\nhello\r\n",
+            |value: Push<PushEvent>| {
+                assert_eq!(
+                    value.0,
+                    PushEvent::PMessage {
+                        pattern: "pat".into(),
+                        channel: "ch".into(),
+                        payload: Some("hello".into()),
+                    }
+                )
+            },
+        );
+        // missing trailing payload
+        test_deserialize(
+            b">3\r\n+pmessage\r\n+pat\r\n+ch\r\n",
+            |value: Push<PushEvent>| {
+                assert_eq!(
+                    value.0,
+                    PushEvent::PMessage {
+                        pattern: "pat".into(),
+                        channel: "ch".into(),
+                        payload: None,
+                    }
+                )
+            },
+        );
+        test_deserialize(
+            b">3\r\n+message\r\n+ch\r\n$2\r\nhi\r\n",
+            |value: Push<PushEvent>| {
+                assert_eq!(
+                    value.0,
+                    PushEvent::Message {
+                        channel: "ch".into(),
+                        payload: "hi".into(),
+                    }
+                )
+            },
+        );
+        test_deserialize(b"*3\r\n+subscribe\r\n+ch\r\n:1\r\n", |value: PushEvent| {
+            assert_eq!(value, PushEvent::Subscribe("ch".into(), 1))
+        });
+        test_deserialize(b">2\r\n+invalidate\r\n_\r\n", |value: Push<PushEvent>| {
+            assert_eq!(value.0, PushEvent::Invalidate(None))
+        });
+        test_deserialize(
+            b">2\r\n+invalidate\r\n*1\r\n+key\r\n",
+            |value: Push<PushEvent>| {
+                assert_eq!(value.0, PushEvent::Invalidate(Some(vec!["key".into()])))
+            },
+        );
+        test_deserialize(b"*2\r\n+ping\r\n+ignored\r\n", |value: PushEvent| {
+            assert_eq!(value, PushEvent::Ping)
+        });
+
+        test_deserialize_result(b"*0\r\n", |value: Result<PushEvent>| {
+            assert_eq!(value.unwrap_err(), Error::ExpectedValue("variant name"));
+        });
+        test_deserialize_result(b"*2\r\n+other\r\n:1\r\n", |value: Result<PushEvent>| {
+            assert!(matches!(value, Err(Error::Custom(_))));
+        });
+    }
 }
@@ -5,19 +5,41 @@ pub enum Error {
     IO(std::io::Error),
     /// Unexpected EOF encountered
     EOF,
-    /// Expected a marker of one type but received something else
-    ExpectedMarker(&'static str),
+    /// Expected a marker of one type but received the `found` byte
+    #[non_exhaustive]
+    UnexpectedMarker {
+        /// marker expected, e.g. `'#' for bool`
+        expected: &'static str,
+        /// byte received instead
+        found: u8,
+    },
     /// Expected a value of one type but received something else
     ExpectedValue(&'static str),
     /// Received an unexpected value
     UnexpectedValue(&'static str),
-    /// Failed to convert the underlying bytes to utf8, value return the offset right before the
-    /// invalid utf8
-    UTF8(usize),
+    /// Failed to convert the underlying bytes to utf8, with the offending byte
+    #[non_exhaustive]
+    InvalidUtf8 {
+        /// offset right before the invalid utf8
+        offset: usize,
+        /// first byte of the invalid sequence
+        byte: u8,
+    },
     /// Failed to parse a float value
     Parse,
     /// Received a NaN
     NaN,
+    /// Received a length or line exceeding the configured limit
+    LengthLimitExceeded,
+    /// Received aggregates nested deeper than the configured limit
+    DepthLimitExceeded,
+    /// The underlying Read returned [`std::io::ErrorKind::WouldBlock`] before
+    /// the frame was complete. Fatal for
+    /// [`Deserializer::from_read`](crate::Deserializer::from_read), see
+    /// [`Error::needs_more_data`]
+    Incomplete,
+    /// Input left after the expected end, with the number of bytes left if known
+    TrailingData(Option<usize>),
     /// Custom error from serialize/deserialize
     Custom(String),
 }
@@ -33,9 +55,12 @@ impl Error {
         Error::EOF
     }
 
-    /// Expected a marker of one type but received something else
-    pub fn expected_marker(expecting: &'static str) -> Self {
-        Error::ExpectedMarker(expecting)
+    /// Expected a marker of one type but received the `found` byte
+    pub fn expected_marker_found(expecting: &'static str, found: u8) -> Self {
+        Error::UnexpectedMarker {
+            expected: expecting,
+            found,
+        }
     }
 
     /// Expected a value of one type but received something else
@@ -48,10 +73,10 @@ impl Error {
         Error::UnexpectedValue(unexpected)
     }
 
-    /// Failed to convert the underlying bytes to utf8, value return the offset right before the
-    /// invalid utf8
-    pub fn utf8(valid_up_to: usize) -> Self {
-        Error::UTF8(valid_up_to)
+    /// Failed to convert the underlying bytes to utf8, `offset` is right before the invalid
+    /// `byte`
+    pub fn utf8(offset: usize, byte: u8) -> Self {
+        Error::InvalidUtf8 { offset, byte }
     }
 
     /// Failed to parse a float value
@@ -68,6 +93,76 @@ impl Error {
     pub fn nan() -> Self {
         Error::NaN
     }
+
+    /// Received a length or line exceeding the configured limit
+    pub fn length_limit_exceeded() -> Self {
+        Error::LengthLimitExceeded
+    }
+
+    /// Received aggregates nested deeper than the configured limit
+    pub fn depth_limit_exceeded() -> Self {
+        Error::DepthLimitExceeded
+    }
+
+    /// The underlying Read would block before the frame was complete
+    pub fn incomplete() -> Self {
+        Error::Incomplete
+    }
+
+    /// Input left after the expected end
+    pub fn trailing_data(remaining: Option<usize>) -> Self {
+        Error::TrailingData(remaining)
+    }
+
+    /// Returns true if the input ended ([`Error::EOF`]) or would block
+    /// ([`Error::Incomplete`]) in the middle of a frame, the frame may parse
+    /// once more bytes arrive.
+    ///
+    /// Only a slice can be retried: parse the received bytes again from the
+    /// start once more arrive. [`Error::Incomplete`] from
+    /// [`Deserializer::from_read`](crate::Deserializer::from_read) is fatal,
+    /// the bytes already taken from the Read source are lost and the
+    /// deserializer must be dropped. Read non-blocking streams with
+    /// [`FrameReader`](crate::FrameReader), which buffers a partial frame so
+    /// [`FrameReader::read_frame`](crate::FrameReader::read_frame) can be
+    /// retried, or accumulate the bytes and parse them with
+    /// [`Deserializer::from_slice`](crate::Deserializer::from_slice),
+    /// advancing the buffer by
+    /// [`Deserializer::get_consumed_bytes`](crate::Deserializer::get_consumed_bytes)
+    /// on success.
+    pub fn needs_more_data(&self) -> bool {
+        matches!(self, Error::EOF | Error::Incomplete)
+    }
+
+    /// Returns true if the input ended in the middle of a frame, see
+    /// [`Error::needs_more_data`] to also cover non-blocking reads
+    pub fn is_eof(&self) -> bool {
+        matches!(self, Error::EOF)
+    }
+
+    /// Returns true if the error comes from the underlying io
+    pub fn is_io(&self) -> bool {
+        matches!(self, Error::IO(_))
+    }
+
+    /// Returns true if the input doesn't hold the expected data, either
+    /// malformed RESP or RESP not matching the target type, retrying with
+    /// more bytes won't help
+    pub fn is_protocol(&self) -> bool {
+        matches!(
+            self,
+            Error::UnexpectedMarker { .. }
+                | Error::ExpectedValue(_)
+                | Error::UnexpectedValue(_)
+                | Error::InvalidUtf8 { .. }
+                | Error::Parse
+                | Error::NaN
+                | Error::LengthLimitExceeded
+                | Error::DepthLimitExceeded
+                | Error::TrailingData(_)
+                | Error::Custom(_)
+        )
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -75,12 +170,23 @@ impl std::fmt::Display for Error {
         match self {
             Error::IO(e) => write!(f, "IO Error:\n{}", e),
             Error::EOF => write!(f, "Reach unexpected EOF"),
-            Error::ExpectedMarker(m) => write!(f, "expected marker {}, received other", m),
+            Error::UnexpectedMarker { expected, found } => {
+                write!(f, "expected {}, found '{}'", expected, found.escape_ascii())
+            }
             Error::ExpectedValue(v) => write!(f, "expected value {}, received other", v),
             Error::UnexpectedValue(v) => write!(f, "received unexpected value {}", v),
-            Error::UTF8(_) => write!(f, "failed to parse input as utf8"),
+            Error::InvalidUtf8 { offset, byte } => write!(
+                f,
+                "failed to parse input as utf8, invalid byte 0x{:02x} at offset {}",
+                byte, offset
+            ),
             Error::Parse => write!(f, "failed to parse number or overflow"),
             Error::NaN => write!(f, "NaN received"),
+            Error::LengthLimitExceeded => write!(f, "length exceeds limit"),
+            Error::DepthLimitExceeded => write!(f, "depth exceeds limit"),
+            Error::Incomplete => write!(f, "incomplete frame, read would block"),
+            Error::TrailingData(Some(n)) => write!(f, "{} bytes of trailing data", n),
+            Error::TrailingData(None) => write!(f, "trailing data"),
             Error::Custom(c) => write!(f, "Custom error:\n{}", c),
         }
     }
@@ -88,6 +194,41 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// Compares variants and their payloads, [`Error::IO`] are compared by their
+/// [`std::io::ErrorKind`] since [`std::io::Error`] isn't comparable
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::IO(a), Error::IO(b)) => a.kind() == b.kind(),
+            (Error::EOF, Error::EOF) => true,
+            (Error::UnexpectedMarker { expected, found }, other) => matches!(
+                other,
+                Error::UnexpectedMarker { expected: e, found: f } if e == expected && f == found
+            ),
+            (Error::ExpectedValue(a), Error::ExpectedValue(b)) => a == b,
+            (Error::UnexpectedValue(a), Error::UnexpectedValue(b)) => a == b,
+            (Error::InvalidUtf8 { offset, byte }, other) => matches!(
+                other,
+                Error::InvalidUtf8 { offset: o, byte: b } if o == offset && b == byte
+            ),
+            (Error::Parse, Error::Parse) => true,
+            (Error::NaN, Error::NaN) => true,
+            (Error::LengthLimitExceeded, Error::LengthLimitExceeded) => true,
+            (Error::DepthLimitExceeded, Error::DepthLimitExceeded) => true,
+            (Error::Incomplete, Error::Incomplete) => true,
+            (Error::TrailingData(a), Error::TrailingData(b)) => a == b,
+            (Error::Custom(a), Error::Custom(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::io(err)
+    }
+}
+
 impl serde::de::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
@@ -99,3 +240,118 @@ impl serde::de::Error for Error {
 
 /// Result from serialize/deserialize
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Converts bytes to str, the error carries the offset and the first invalid byte
+pub(crate) fn str_from_utf8(b: &[u8]) -> Result<&str> {
+    std::str::from_utf8(b).map_err(|e| {
+        // an invalid or truncated sequence always starts at `valid_up_to`
+        let offset = e.valid_up_to();
+        Error::utf8(offset, b[offset])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_eq() {
+        assert_eq!(
+            Error::expected_marker_found("bool", b'+'),
+            Error::UnexpectedMarker {
+                expected: "bool",
+                found: b'+'
+            }
+        );
+        assert_ne!(
+            Error::expected_marker_found("bool", b'+'),
+            Error::expected_marker_found("bool", b':')
+        );
+        assert_ne!(
+            Error::expected_marker_found("bool", b'+'),
+            Error::ExpectedValue("bool")
+        );
+        assert_eq!(Error::utf8(1, 0xff), str_from_utf8(b"a\xff").unwrap_err());
+        assert_ne!(Error::utf8(1, 0xff), Error::utf8(1, 0xfe));
+        assert_eq!(Error::Custom("a".into()), Error::Custom("a".into()));
+        assert_ne!(Error::Custom("a".into()), Error::Custom("b".into()));
+        assert_eq!(
+            Error::io(std::io::Error::other("a")),
+            Error::io(std::io::Error::other("b"))
+        );
+        assert_ne!(
+            Error::io(std::io::ErrorKind::UnexpectedEof.into()),
+            Error::io(std::io::ErrorKind::Other.into())
+        );
+        assert_ne!(Error::eof(), Error::nan());
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        fn read_reply(input: &[u8]) -> Result<u64> {
+            let mut buf = Vec::new();
+            std::io::Read::read_to_end(&mut &input[..], &mut buf)?;
+            crate::from_slice(&buf)
+        }
+        assert_eq!(read_reply(b":1\r\n").unwrap(), 1);
+
+        let err: Error = std::io::Error::from(std::io::ErrorKind::BrokenPipe).into();
+        assert!(matches!(err, Error::IO(e) if e.kind() == std::io::ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn test_classification() {
+        let eof = crate::from_slice::<_, u64>(b":12").unwrap_err();
+        assert!(eof.is_eof());
+        assert!(!eof.is_io());
+        assert!(!eof.is_protocol());
+        assert!(!Error::incomplete().is_eof());
+
+        let io = Error::io(std::io::ErrorKind::BrokenPipe.into());
+        assert!(io.is_io());
+        assert!(!io.is_eof());
+        assert!(!io.is_protocol());
+
+        let protocol = crate::from_slice::<_, u64>(b"?12\r\n").unwrap_err();
+        assert!(protocol.is_protocol());
+        assert!(!protocol.is_eof());
+        assert!(!protocol.is_io());
+        assert!(Error::utf8(0, 0xff).is_protocol());
+        assert!(Error::trailing_data(None).is_protocol());
+
+        assert!(Error::Custom("unknown variant".into()).is_protocol());
+        assert!(!Error::incomplete().is_protocol());
+    }
+
+    #[test]
+    fn test_utf8_display() {
+        let err = str_from_utf8(b"ab\xffc").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidUtf8 {
+                offset: 2,
+                byte: 0xff,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "failed to parse input as utf8, invalid byte 0xff at offset 2"
+        );
+
+        // truncated sequence at the end
+        let err = str_from_utf8(b"abc\xe2\x82").unwrap_err();
+        assert_eq!(err, Error::utf8(3, 0xe2));
+    }
+
+    #[test]
+    fn test_expected_marker_display() {
+        let err = Error::expected_marker_found("':' for signed integer", b'+');
+        assert_eq!(
+            err.to_string(),
+            "expected ':' for signed integer, found '+'"
+        );
+        let err = Error::expected_marker_found("'#' for bool", b'\r');
+        assert_eq!(err.to_string(), "expected '#' for bool, found '\\r'");
+    }
+}
@@ -0,0 +1,370 @@
+use std::io::{self, Read};
+
+use serde::Deserialize;
+
+use crate::{
+    de::{FrameScan, Options},
+    Deserializer, Error, Result,
+};
+
+const READ_CHUNK: usize = 4096;
+
+/// Reads complete RESP frames from a stream, such as a `TcpStream`, into
+/// an internal buffer before deserializing them.
+///
+/// A frame split across reads is kept buffered until its remaining bytes
+/// arrive, so on a non-blocking stream [`Error::Incomplete`] can be retried
+/// without losing data, unlike [`Deserializer::from_read`]. Values are
+/// deserialized from the buffer and can borrow from it. Pushes are
+/// returned as frames like replies, e.g. into [`Value::Push`](crate::Value::Push).
+///
+/// Use [`FrameReader::with_max_length`], [`FrameReader::with_max_depth`]
+/// and [`FrameReader::with_max_line_len`] with untrusted streams, a frame
+/// is buffered until it is complete.
+///
+/// ```rust
+/// use deseresp::FrameReader;
+///
+/// let mut frames = FrameReader::new(&b"*2\r\n+a\r\n:1\r\n+OK\r\n"[..]);
+/// let value: (&str, u64) = frames.read_frame().unwrap();
+/// assert_eq!(value, ("a", 1));
+/// let value: &str = frames.read_frame().unwrap();
+/// assert_eq!(value, "OK");
+/// ```
+pub struct FrameReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    // bytes of the last frame returned, dropped before the next read
+    consumed: usize,
+    // progress through the frame being received
+    scan: FrameScan,
+    // options of the Deserializers reading the buffer
+    options: Options,
+    max_line_len: usize,
+    // bytes of the line being received searched for its end so far
+    searched: usize,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Creates a [`FrameReader`] from an underlying [`Read`]
+    pub fn new(reader: R) -> Self {
+        FrameReader {
+            reader,
+            buf: Vec::new(),
+            consumed: 0,
+            scan: FrameScan::default(),
+            // pushes are returned as frames
+            options: Options {
+                skip_push: false,
+                ..Options::default()
+            },
+            max_line_len: usize::MAX,
+            searched: 0,
+        }
+    }
+
+    /// Limits the length of strings and aggregates of a frame, as
+    /// [`Deserializer::with_max_length`], checked as their header arrives
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.options.max_length = max_length;
+        self
+    }
+
+    /// Limits the nesting depth of a frame, as [`Deserializer::with_max_depth`]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    /// Limits the length of lines (simple strings, numbers, headers, ...)
+    /// of a frame, as [`Deserializer::with_max_line_len`]. An unterminated
+    /// line is rejected once it exceeds the limit instead of being
+    /// buffered until the stream ends.
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    /// Reads the next complete frame, then deserializes it into `T`.
+    ///
+    /// Errors with [`Error::Incomplete`] if the underlying [`Read`] would
+    /// block before the frame is complete, the bytes read so far stay
+    /// buffered for the next call. Errors with [`Error::EOF`] if the stream
+    /// ends, [`FrameReader::buffered`] tells whether it ended mid-frame.
+    pub fn read_frame<'a, T>(&'a mut self) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        self.buf.drain(..self.consumed);
+        self.consumed = 0;
+        self.searched = 0;
+        while !(self.line_received()? && self.scan_frame()?) {
+            self.fill()?;
+        }
+        self.consumed = std::mem::take(&mut self.scan).scanned;
+
+        let mut deserializer =
+            Deserializer::from_slice(&self.buf[..self.consumed]).with_options(self.options);
+        T::deserialize(&mut deserializer)
+    }
+
+    /// Bytes read from the stream but not returned as a frame yet
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf[self.consumed..]
+    }
+
+    /// Returns the underlying Read
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps the underlying Read, dropping the buffered bytes
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Scans the buffered bytes not scanned yet, returns whether the
+    /// frame is complete
+    fn scan_frame(&mut self) -> Result<bool> {
+        let mut deserializer =
+            Deserializer::from_slice(&self.buf[self.scan.scanned..]).with_options(self.options);
+        match deserializer.scan_frame(&mut self.scan) {
+            Ok(()) => Ok(true),
+            Err(e) if e.is_eof() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the first line of the element to scan next is received,
+    /// the element can't be scanned further before. The bytes searched
+    /// for the line end aren't searched again.
+    fn line_received(&mut self) -> Result<bool> {
+        let start = self.scan.scanned.max(self.searched);
+        // marker, line and `\r\n`
+        let limit = self
+            .scan
+            .scanned
+            .saturating_add(self.max_line_len)
+            .saturating_add(3);
+        let end = self.buf.len().min(limit);
+        match self.buf[start..end].iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                self.searched = start + pos;
+                Ok(true)
+            }
+            None if end == limit => Err(Error::length_limit_exceeded()),
+            None => {
+                self.searched = end;
+                Ok(false)
+            }
+        }
+    }
+
+    fn fill(&mut self) -> Result<()> {
+        let mut chunk = [0; READ_CHUNK];
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => return Err(Error::eof()),
+                Ok(n) => {
+                    self.buf.extend_from_slice(&chunk[..n]);
+                    return Ok(());
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Err(Error::incomplete()),
+                Err(e) => return Err(Error::io(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::Value;
+
+    /// Yields one chunk per read, `None` chunks would block
+    struct ChunkedReader(VecDeque<Option<&'static [u8]>>);
+
+    impl Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.pop_front() {
+                Some(Some(chunk)) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                Some(None) => Err(io::ErrorKind::WouldBlock.into()),
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_in_two_chunks() {
+        let chunks = [Some(&b"*2\r\n$5\r\nhel"[..]), Some(b"lo\r\n:42\r\n")];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into()));
+        let value: (&str, u64) = frames.read_frame().unwrap();
+        assert_eq!(value, ("hello", 42));
+        assert!(frames.buffered().is_empty());
+        assert_eq!(frames.read_frame::<u64>(), Err(Error::EOF));
+    }
+
+    #[test]
+    fn test_frame_resumes_after_would_block() {
+        let chunks = [
+            Some(&b"+OK\r\n%1\r\n+a"[..]),
+            None,
+            Some(b"\r\n:1\r\n"),
+            Some(b":2"),
+        ];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into()));
+        assert_eq!(frames.read_frame::<String>().unwrap(), "OK");
+        assert_eq!(frames.read_frame::<(String, u64)>(), Err(Error::Incomplete));
+        assert_eq!(frames.buffered(), b"%1\r\n+a");
+        let value: std::collections::BTreeMap<&str, u64> = frames.read_frame().unwrap();
+        assert_eq!(value.get("a"), Some(&1));
+
+        // stream ended mid-frame
+        assert_eq!(frames.read_frame::<u64>(), Err(Error::EOF));
+        assert_eq!(frames.buffered(), b":2");
+    }
+
+    /// Reads the next frame, retrying while the reader would block
+    fn read_blocking<T: DeserializeOwned>(frames: &mut FrameReader<ChunkedReader>) -> Result<T> {
+        loop {
+            match frames.read_frame() {
+                Err(Error::Incomplete) => {}
+                r => return r,
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_byte_by_byte() {
+        let map: &[u8] = b"|1\r\n+ttl\r\n:10\r\n%2\r\n+a\r\n*2\r\n:1\r\n*0\r\n+b\r\n$2\r\nhi\r\n";
+        let streamed: &[u8] = b"*?\r\n:1\r\n$?\r\n;2\r\nhi\r\n;0\r\n.\r\n";
+        let input = [map, streamed, b">2\r\n+message\r\n+hello\r\n"]
+            .concat()
+            .leak();
+        let chunks = input.chunks(1).flat_map(|chunk| [Some(chunk), None]);
+        let mut frames = FrameReader::new(ChunkedReader(chunks.collect()));
+
+        let value: Value = read_blocking(&mut frames).unwrap();
+        assert_eq!(value, crate::from_slice::<_, Value>(map).unwrap());
+        let value: Value = read_blocking(&mut frames).unwrap();
+        let hi = Value::BlobString(b"hi".to_vec());
+        assert_eq!(value, Value::Array(vec![Value::Integer(1), hi]));
+        assert_eq!(read_blocking::<Value>(&mut frames).unwrap(), push());
+        assert_eq!(read_blocking::<Value>(&mut frames), Err(Error::EOF));
+        assert!(frames.buffered().is_empty());
+    }
+
+    #[test]
+    fn test_frame_scan_resumes() {
+        let chunks = [Some(&b"*3\r\n:1\r\n:2"[..]), None, Some(b"\r\n:3\r\n")];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into()));
+        assert_eq!(frames.read_frame::<Vec<u64>>(), Err(Error::Incomplete));
+        // the complete elements are not scanned again
+        assert_eq!(frames.scan.scanned, b"*3\r\n:1\r\n".len());
+        assert_eq!(frames.read_frame::<Vec<u64>>().unwrap(), [1, 2, 3]);
+        assert_eq!(frames.scan.scanned, 0);
+    }
+
+    fn push() -> Value {
+        Value::Push(vec![
+            Value::SimpleString("message".into()),
+            Value::SimpleString("hello".into()),
+        ])
+    }
+
+    #[test]
+    fn test_frame_push() {
+        let chunks = [Some(&b">2\r\n+message\r\n+hello\r\n+OK\r\n"[..])];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into()));
+        assert_eq!(frames.read_frame::<Value>().unwrap(), push());
+        assert_eq!(frames.read_frame::<String>().unwrap(), "OK");
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        let chunks = [Some(&b"$99999999999\r\n"[..]), None];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into())).with_max_length(1024);
+        assert_eq!(
+            frames.read_frame::<Value>(),
+            Err(Error::LengthLimitExceeded)
+        );
+
+        let chunks = [Some(&b"*1\r\n*1\r\n*1\r\n"[..]), None];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into())).with_max_depth(2);
+        assert_eq!(frames.read_frame::<Value>(), Err(Error::DepthLimitExceeded));
+
+        let chunks = [Some(&b"*1\r\n*1\r\n:1\r\n"[..])];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into())).with_max_depth(2);
+        let value: Vec<Vec<u64>> = frames.read_frame().unwrap();
+        assert_eq!(value, [[1]]);
+    }
+
+    /// Sends `+` then `a` forever, a line that never ends
+    struct EndlessLine(usize);
+
+    impl Read for EndlessLine {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            buf.fill(b'a');
+            if self.0 == 0 {
+                buf[0] = b'+';
+            }
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+    }
+
+    #[test]
+    fn test_frame_endless_line() {
+        let mut frames = FrameReader::new(EndlessLine(0)).with_max_line_len(16);
+        assert_eq!(
+            frames.read_frame::<Value>(),
+            Err(Error::LengthLimitExceeded)
+        );
+        assert!(frames.get_ref().0 <= READ_CHUNK);
+
+        // lines up to the limit are read
+        let chunks = [Some(
+            &b"*2\r\n+0123456789abcdef\r\n:1234567890123456\r\n"[..],
+        )];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into())).with_max_line_len(16);
+        let value: (String, u64) = frames.read_frame().unwrap();
+        assert_eq!(value, ("0123456789abcdef".into(), 1234567890123456));
+    }
+
+    #[test]
+    fn test_frame_streamed_string_resumes() {
+        let chunks = [
+            Some(&b"$?\r\n;2\r\nhi\r\n;3\r\nth"[..]),
+            None,
+            Some(b"e\r\n;0\r\n"),
+        ];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into()));
+        assert_eq!(frames.read_frame::<String>(), Err(Error::Incomplete));
+        // the complete chunks are not scanned again
+        assert_eq!(frames.scan.scanned, b"$?\r\n;2\r\nhi\r\n".len());
+        assert_eq!(frames.read_frame::<String>().unwrap(), "hithe");
+
+        let chunks = [Some(&b"$?\r\n;3\r\nabc\r\n;3\r\nabc\r\n"[..]), None];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into())).with_max_length(4);
+        assert_eq!(
+            frames.read_frame::<String>(),
+            Err(Error::LengthLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_frame_invalid() {
+        let chunks = [Some(&b"*2\r\n?\r\n"[..])];
+        let mut frames = FrameReader::new(ChunkedReader(chunks.into()));
+        let err = frames.read_frame::<Vec<u64>>().unwrap_err();
+        assert!(!err.needs_more_data(), "{:?}", err);
+    }
+}
@@ -1,12 +1,30 @@
-#[doc = include_str!("../README.md")]
+#![doc = include_str!("../README.md")]
+
+#[cfg(feature = "heapless")]
+pub mod bounded;
+mod command;
 mod de;
 mod error;
+mod frame_reader;
+pub mod markers;
+mod push_stream;
+pub mod radix;
 mod ser;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod time;
+mod token;
 pub mod types;
+mod value;
 
-pub use de::{from_read, from_slice, Deserializer};
+pub use command::{encode_pipeline, Command};
+pub use de::{from_read, from_slice, from_slice_checked, from_slice_result, Deserializer};
 pub use error::{Error, Result};
-pub use ser::{to_vec, Serializer};
+pub use frame_reader::FrameReader;
+pub use push_stream::PushStream;
+pub use ser::{to_vec, to_vec_with, Serializer, SerializerOptions};
+pub use token::{tokenize, write_tokens, Token};
+pub use value::{from_value, to_debug_string, AnyScalar, Frame, RespType, Value, ValueRef};
 
 #[cfg(test)]
 pub(crate) mod test_utils {
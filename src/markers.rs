@@ -0,0 +1,44 @@
+//! RESP3 type markers, the first byte of every value
+//!
+//! ```rust
+//! use deseresp::markers;
+//!
+//! let reply = b"*2\r\n:1\r\n:2\r\n";
+//! assert_eq!(reply[0], markers::ARRAY);
+//! ```
+
+/// `+<string>\r\n`
+pub const SIMPLE_STRING: u8 = b'+';
+/// `-<string>\r\n`
+pub const SIMPLE_ERROR: u8 = b'-';
+/// `$<len>\r\n<bytes>\r\n`
+pub const BLOB_STRING: u8 = b'$';
+/// `!<len>\r\n<bytes>\r\n`
+pub const BLOB_ERROR: u8 = b'!';
+/// `=<len>\r\n<format>:<text>\r\n`
+pub const VERBATIM_STRING: u8 = b'=';
+/// `:<number>\r\n`
+pub const INTEGER: u8 = b':';
+/// `,<double>\r\n`
+pub const DOUBLE: u8 = b',';
+/// `#t\r\n` or `#f\r\n`
+pub const BOOLEAN: u8 = b'#';
+/// `(<big number>\r\n`
+pub const BIG_NUMBER: u8 = b'(';
+/// `_\r\n`
+pub const NULL: u8 = b'_';
+/// `*<len>\r\n`, followed by len values
+pub const ARRAY: u8 = b'*';
+/// `~<len>\r\n`, followed by len values
+pub const SET: u8 = b'~';
+/// `><len>\r\n`, followed by len values
+pub const PUSH: u8 = b'>';
+/// `%<len>\r\n`, followed by len key/value pairs
+pub const MAP: u8 = b'%';
+/// `|<len>\r\n`, followed by len key/value pairs, then the attributed value
+pub const ATTRIBUTE: u8 = b'|';
+/// `;<len>\r\n<bytes>\r\n`, a chunk of a streamed string (`$?\r\n`),
+/// the empty chunk ends the string
+pub const STREAMED_STRING_CHUNK: u8 = b';';
+/// `.\r\n`, ends a streamed aggregate (`*?\r\n`, ...)
+pub const STREAMED_AGGREGATE_END: u8 = b'.';
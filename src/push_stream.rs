@@ -0,0 +1,176 @@
+use std::io::Read;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::de::ReadReader;
+use crate::types::PushOrValue;
+use crate::{Deserializer, Error, Result, Value};
+
+/// Reads an open-ended stream of frames from a connection, such as a
+/// subscribed pubsub connection, deserializing pushes (`>`) into `P`.
+///
+/// Iterating yields [`PushOrValue::Push`] for pushes and
+/// [`PushOrValue::Value`] for the command replies interleaved with them,
+/// e.g. the reply to a `PING`, use [`PushStream::next_push`] to skip the
+/// replies instead. The stream ends once the connection is closed between
+/// two frames.
+///
+/// Each frame is read whole before being deserialized, so a push `P`
+/// doesn't accept, such as an unknown kind, is an error for that frame
+/// only and the stream goes on. Malformed input or an I/O error ends the
+/// stream since it can't be resynced.
+///
+/// ```rust
+/// use deseresp::PushStream;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// #[serde(rename_all = "lowercase")]
+/// enum Message {
+///     Message(String, String),
+/// }
+///
+/// let input = &b">3\r\n+message\r\n+ch\r\n+hi\r\n+PONG\r\n"[..];
+/// let mut stream = PushStream::<_, Message>::new(input);
+/// let push = stream.next().unwrap().unwrap().into_push();
+/// assert_eq!(push, Some(Message::Message("ch".into(), "hi".into())));
+/// assert!(stream.next().unwrap().unwrap().into_value().is_some());
+/// assert!(stream.next().is_none());
+/// ```
+pub struct PushStream<R: Read, P> {
+    de: Deserializer<ReadReader<R>>,
+    done: bool,
+    _push: PhantomData<fn() -> P>,
+}
+
+impl<R: Read, P: DeserializeOwned> PushStream<R, P> {
+    /// Creates a [`PushStream`] from an underlying [`Read`]
+    pub fn new(reader: R) -> Self {
+        PushStream::from_deserializer(Deserializer::from_read(reader))
+    }
+
+    /// Creates a [`PushStream`] from a configured [`Deserializer`], e.g.
+    /// one with [`Deserializer::with_max_line_len`]
+    pub fn from_deserializer(de: Deserializer<ReadReader<R>>) -> Self {
+        PushStream {
+            de,
+            done: false,
+            _push: PhantomData,
+        }
+    }
+
+    /// Reads frames until the next push, skipping command replies
+    pub fn next_push(&mut self) -> Option<Result<P>> {
+        loop {
+            match self.next()? {
+                Ok(PushOrValue::Push(p)) => return Some(Ok(p)),
+                Ok(PushOrValue::Value(_)) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Unwraps the underlying [`Deserializer`]
+    pub fn into_inner(self) -> Deserializer<ReadReader<R>> {
+        self.de
+    }
+}
+
+impl<R: Read, P: DeserializeOwned> Iterator for PushStream<R, P> {
+    type Item = Result<PushOrValue<P, Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let r = match self.de.expect_eof() {
+            Ok(()) => {
+                self.done = true;
+                return None;
+            }
+            Err(Error::TrailingData(_)) => self.de.deserialize_bounded(&mut Vec::new()),
+            Err(e) => Err(e),
+        };
+        match r {
+            Ok(r) => Some(r),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum PubSub {
+        Subscribe(String, u64),
+        Message(String, String),
+        PMessage(String, String, String),
+    }
+
+    #[test]
+    fn test_push_stream() {
+        let input = b">3\r\n+subscribe\r\n+ch\r\n:1\r\n\
+            >3\r\n+message\r\n+ch\r\n+hello\r\n\
+            +PONG\r\n\
+            >4\r\n+pmessage\r\n+c*\r\n+ch\r\n$5\r\nworld\r\n";
+        let mut stream = PushStream::<_, PubSub>::new(&input[..]);
+
+        let push = stream.next().unwrap().unwrap().into_push();
+        assert_eq!(push, Some(PubSub::Subscribe("ch".into(), 1)));
+        let push = stream.next().unwrap().unwrap().into_push();
+        assert_eq!(push, Some(PubSub::Message("ch".into(), "hello".into())));
+        let reply = stream.next().unwrap().unwrap().into_value();
+        assert_eq!(reply, Some(Value::SimpleString("PONG".into())));
+        let push = stream.next().unwrap().unwrap().into_push();
+        let expected = PubSub::PMessage("c*".into(), "ch".into(), "world".into());
+        assert_eq!(push, Some(expected));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_push_stream_next_push() {
+        let input = b"+OK\r\n>3\r\n+message\r\n+a\r\n+1\r\n:5\r\n>3\r\n+message\r\n+b\r\n+2\r\n";
+        let mut stream = PushStream::<_, PubSub>::new(&input[..]);
+        let pushes: Vec<_> = std::iter::from_fn(|| stream.next_push())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            pushes,
+            [
+                PubSub::Message("a".into(), "1".into()),
+                PubSub::Message("b".into(), "2".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_push_stream_unknown_push() {
+        let input = b">3\r\n+smessage\r\n+ch\r\n+hi\r\n\
+            >2\r\n+invalidate\r\n*1\r\n+key\r\n\
+            >3\r\n+message\r\n+ch\r\n+hello\r\n";
+        let mut stream = PushStream::<_, PubSub>::new(&input[..]);
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().unwrap().is_err());
+        let push = stream.next().unwrap().unwrap().into_push();
+        assert_eq!(push, Some(PubSub::Message("ch".into(), "hello".into())));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_push_stream_error_ends() {
+        let input = b">3\r\n+message\r\n+a\r\n+1\r\n>3\r\n+message\r\n+a";
+        let mut stream = PushStream::<_, PubSub>::new(&input[..]);
+        assert!(stream.next_push().unwrap().is_ok());
+        assert_eq!(stream.next_push().unwrap(), Err(Error::EOF));
+        assert!(stream.next().is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! Adapters reading a simple/blob string as an unsigned number written in a
+//! specific radix, to be used with `#[serde(with = "...")]`.
+//!
+//! ```rust
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Reply {
+//!     #[serde(with = "deseresp::radix::hex")]
+//!     id: u64,
+//! }
+//!
+//! let reply: Reply = deseresp::from_slice(b"%1\r\n+id\r\n+ff\r\n").unwrap();
+//! assert_eq!(reply.id, 255);
+//! ```
+
+use serde::de::{self, Visitor};
+
+struct RadixVisitor {
+    radix: u32,
+}
+
+impl<'de> Visitor<'de> for RadixVisitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting str of base {} number", self.radix)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u64::from_str_radix(v, self.radix)
+            .map_err(|_e| de::Error::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+macro_rules! impl_radix {
+    ($mod_name:ident, $radix:expr, $fmt:literal, $doc:literal) => {
+        #[doc = $doc]
+        pub mod $mod_name {
+            use serde::{Deserializer, Serializer};
+
+            /// Serialize u64 as a simple string in this radix
+            pub fn serialize<S>(v: &u64, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&format!($fmt, v))
+            }
+
+            /// Deserialize u64 from a simple/blob string in this radix
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_str(super::RadixVisitor { radix: $radix })
+            }
+        }
+    };
+}
+
+impl_radix!(
+    hex,
+    16,
+    "{:x}",
+    "Hexadecimal string, e.g. `+1a\\r\\n` is 26"
+);
+impl_radix!(oct, 8, "{:o}", "Octal string, e.g. `+17\\r\\n` is 15");
+impl_radix!(bin, 2, "{:b}", "Binary string, e.g. `+101\\r\\n` is 5");
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        test_utils::{test_deserialize, test_deserialize_result},
+        to_vec, Error,
+    };
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Hex(#[serde(with = "super::hex")] u64);
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Oct(#[serde(with = "super::oct")] u64);
+    #[derive(Deserialize, Serialize, PartialEq, Debug)]
+    struct Bin(#[serde(with = "super::bin")] u64);
+
+    #[test]
+    fn test_deserialize_radix() {
+        test_deserialize(b"+1a\r\n", |value: Hex| {
+            assert_eq!(value, Hex(26));
+        });
+        test_deserialize(b"$2\r\nFF\r\n", |value: Hex| {
+            assert_eq!(value, Hex(255));
+        });
+        test_deserialize(b"+17\r\n", |value: Oct| {
+            assert_eq!(value, Oct(15));
+        });
+        test_deserialize(b"+101\r\n", |value: Bin| {
+            assert_eq!(value, Bin(5));
+        });
+    }
+
+    #[test]
+    fn test_deserialize_radix_invalid() {
+        test_deserialize_result(b"+1g\r\n", |value: Result<Hex, Error>| {
+            assert!(matches!(value, Err(Error::Custom(_))));
+        });
+        test_deserialize_result(b"+2\r\n", |value: Result<Bin, Error>| {
+            assert!(matches!(value, Err(Error::Custom(_))));
+        });
+        test_deserialize_result(b":26\r\n", |value: Result<Hex, Error>| {
+            assert!(value.is_err());
+        });
+    }
+
+    #[test]
+    fn test_serialize_radix() {
+        assert_eq!(to_vec(&Hex(26)).unwrap(), b"+1a\r\n");
+        assert_eq!(to_vec(&Oct(15)).unwrap(), b"+17\r\n");
+        assert_eq!(to_vec(&Bin(5)).unwrap(), b"+101\r\n");
+    }
+}
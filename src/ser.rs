@@ -1,4 +1,4 @@
-use std::{io::Write, str};
+use std::io::Write;
 
 use serde::{
     ser::{
@@ -9,29 +9,184 @@ use serde::{
 };
 
 use crate::{
+    error::str_from_utf8,
+    markers,
     types::{
-        BLOB_ERROR_TOKEN, BLOB_STRING_TOKEN, PUSH_TOKEN, SIMPLE_ERROR_TOKEN, SIMPLE_STRING_TOKEN,
-        WITH_ATTRIBUTE_TOKEN,
+        BIG_NUMBER_TOKEN, BLOB_ERROR_TOKEN, BLOB_STRING_TOKEN, PUSH_TOKEN, RAW_TOKEN, SET_TOKEN,
+        SIMPLE_ERROR_TOKEN, SIMPLE_STRING_TOKEN, VERBATIM_STRING_TOKEN, WITH_ATTRIBUTE_TOKEN,
     },
     Error,
 };
 
+/// Serializer settings, reusable across [`Serializer`]s.
+///
+/// The setters mirror the ones on [`Serializer`] and can be chained:
+///
+/// ```rust
+/// use deseresp::{to_vec_with, SerializerOptions};
+///
+/// let options = SerializerOptions::new()
+///     .default_string_as_blob(true)
+///     .integral_double_fraction(true);
+/// assert_eq!(to_vec_with(&("SET", 1.0), options).unwrap(), b"*2\r\n$3\r\nSET\r\n,1.0\r\n");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SerializerOptions {
+    bare_unit_variant: bool,
+    promote_crlf_to_blob: bool,
+    string_as_blob: bool,
+    integral_double_fraction: bool,
+    strict_map_keys: bool,
+}
+
+impl SerializerOptions {
+    /// Default options, same as [`Serializer::from_write`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`Serializer::bare_unit_variant`]
+    pub fn bare_unit_variant(mut self, bare: bool) -> Self {
+        self.bare_unit_variant = bare;
+        self
+    }
+
+    /// See [`Serializer::promote_crlf_to_blob`]
+    pub fn promote_crlf_to_blob(mut self, promote: bool) -> Self {
+        self.promote_crlf_to_blob = promote;
+        self
+    }
+
+    /// See [`Serializer::default_string_as_blob`]
+    pub fn default_string_as_blob(mut self, blob: bool) -> Self {
+        self.string_as_blob = blob;
+        self
+    }
+
+    /// See [`Serializer::integral_double_fraction`]
+    pub fn integral_double_fraction(mut self, fraction: bool) -> Self {
+        self.integral_double_fraction = fraction;
+        self
+    }
+
+    /// See [`Serializer::strict_map_keys`]
+    pub fn strict_map_keys(mut self, strict: bool) -> Self {
+        self.strict_map_keys = strict;
+        self
+    }
+}
+
 /// A RESP Serializer
 pub struct Serializer<W> {
     writer: W,
+    options: SerializerOptions,
 }
 
 impl<W: Write> Serializer<W> {
     /// Creates a [`Serializer`] from an underlying [`Write`]
     pub fn from_write(w: W) -> Self {
-        Serializer { writer: w }
+        Self::from_write_with(w, SerializerOptions::default())
+    }
+
+    /// Creates a [`Serializer`] from an underlying [`Write`] with the
+    /// given [`SerializerOptions`]
+    pub fn from_write_with(w: W, options: SerializerOptions) -> Self {
+        Serializer { writer: w, options }
+    }
+
+    /// Current options of this [`Serializer`]
+    pub fn options(&self) -> SerializerOptions {
+        self.options
+    }
+
+    /// Serializes unit enum variants as a bare simple string (`+Variant\r\n`)
+    /// instead of the default 1-length map (`%1\r\n+Variant\r\n_\r\n`).
+    ///
+    /// Status replies such as `+OK`, `+PONG` or the `TYPE` reply (`+string`,
+    /// `+hash`...) use the bare form, the map form is only produced by this
+    /// crate. The deserializer accepts both forms.
+    pub fn bare_unit_variant(mut self, bare: bool) -> Self {
+        self.options.bare_unit_variant = bare;
+        self
+    }
+
+    /// Serializes strings and errors containing CR or LF as blob string
+    /// (`$`) and blob error (`!`) instead of failing.
+    ///
+    /// By default such strings are rejected with
+    /// [`Error::UnexpectedValue`], since written as simple string they
+    /// would end the frame early and corrupt the output.
+    pub fn promote_crlf_to_blob(mut self, promote: bool) -> Self {
+        self.options.promote_crlf_to_blob = promote;
+        self
+    }
+
+    /// Serializes `str`, `String` and `char` as blob string (`$`) instead
+    /// of simple string (`+`).
+    ///
+    /// Command arguments are sent as blob strings, this lets derived
+    /// structs produce them without wrapping every field in
+    /// [`BlobString`](crate::types::owned::BlobString).
+    pub fn default_string_as_blob(mut self, blob: bool) -> Self {
+        self.options.string_as_blob = blob;
+        self
+    }
+
+    /// Writes integral doubles with a fractional digit (`,10.0\r\n`
+    /// instead of `,10\r\n`), for readers that expect a decimal point to
+    /// tell a double from an integer.
+    pub fn integral_double_fraction(mut self, fraction: bool) -> Self {
+        self.options.integral_double_fraction = fraction;
+        self
+    }
+
+    /// Rejects map keys that don't serialize to a string or number scalar
+    /// with [`Error::UnexpectedValue`].
+    ///
+    /// By default keys are written as is, a `None` key (`_`) or an
+    /// aggregate key (`*`, `%`...) is valid RESP3 but most clients can't
+    /// read it back.
+    pub fn strict_map_keys(mut self, strict: bool) -> Self {
+        self.options.strict_map_keys = strict;
+        self
+    }
+
+    /// Serializes a map key, checking it is a scalar when
+    /// [`Serializer::strict_map_keys`] is set
+    fn serialize_map_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        if !self.options.strict_map_keys {
+            return key.serialize(&mut *self);
+        }
+
+        let mut buf = Vec::new();
+        key.serialize(&mut Serializer::from_write_with(&mut buf, self.options))?;
+        match buf.first() {
+            Some(
+                &(markers::SIMPLE_STRING
+                | markers::BLOB_STRING
+                | markers::VERBATIM_STRING
+                | markers::INTEGER
+                | markers::DOUBLE
+                | markers::BOOLEAN
+                | markers::BIG_NUMBER),
+            ) => self.writer.write_all(&buf).map_err(Error::io),
+            _ => Err(Error::unexpected_value("non-scalar map key")),
+        }
     }
 }
 
 /// Serialize to Vec<u8>
 pub fn to_vec<S: Serialize>(s: &S) -> Result<Vec<u8>, Error> {
+    to_vec_with(s, SerializerOptions::default())
+}
+
+/// Serialize to `Vec<u8>` with the given [`SerializerOptions`]
+pub fn to_vec_with<S: Serialize>(s: &S, options: SerializerOptions) -> Result<Vec<u8>, Error> {
     let mut result = Vec::new();
-    let mut serializer = Serializer::from_write(&mut result);
+    let mut serializer = Serializer::from_write_with(&mut result, options);
     s.serialize(&mut serializer)?;
 
     Ok(result)
@@ -55,6 +210,8 @@ pub struct SeqSerializer<'a, W> {
     se: &'a mut Serializer<W>,
     kind: SeqKind,
     with_key: bool,
+    // struct fields left to match the length already written in the header
+    remaining_fields: Option<usize>,
 }
 
 impl<'a, W> SeqSerializer<'a, W> {
@@ -63,6 +220,7 @@ impl<'a, W> SeqSerializer<'a, W> {
             se,
             kind: SeqKind::KnownLength,
             with_key: true,
+            remaining_fields: None,
         }
     }
 
@@ -71,6 +229,7 @@ impl<'a, W> SeqSerializer<'a, W> {
             se,
             kind: SeqKind::UnknownLength,
             with_key: true,
+            remaining_fields: None,
         }
     }
 
@@ -78,15 +237,46 @@ impl<'a, W> SeqSerializer<'a, W> {
         self.with_key = false;
         self
     }
+
+    /// Checks the struct fields or tuple elements serialized match the `len` written in the header,
+    /// a mismatch would corrupt the frame for the reader
+    fn with_field_count(mut self, len: usize) -> Self {
+        self.remaining_fields = Some(len);
+        self
+    }
+
+    fn count_field(&mut self) -> Result<(), Error> {
+        match &mut self.remaining_fields {
+            Some(0) => Err(Error::unexpected_value("fields exceed length")),
+            Some(remaining) => {
+                *remaining -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, W: Write> SeqSerializer<'a, W> {
+    fn end_fields(self) -> Result<(), Error> {
+        match self.remaining_fields {
+            Some(0) | None => {}
+            Some(_) => return Err(Error::unexpected_value("fields less than length")),
+        }
+        match self.kind {
+            SeqKind::UnknownLength => self.se.write_end(),
+            SeqKind::KnownLength => Ok(()),
+        }
+    }
 }
 
 impl<'a, W: Write> SerializeSeq for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(&mut *self.se)
     }
@@ -103,18 +293,16 @@ impl<'a, W: Write> SerializeTuple for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.count_field()?;
         value.serialize(&mut *self.se)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        match self.kind {
-            SeqKind::UnknownLength => self.se.write_end(),
-            SeqKind::KnownLength => Ok(()),
-        }
+        self.end_fields()
     }
 }
 
@@ -122,9 +310,9 @@ impl<'a, W: Write> SerializeTupleStruct for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(&mut *self.se)
     }
@@ -141,9 +329,9 @@ impl<'a, W: Write> SerializeTupleVariant for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(&mut *self.se)
     }
@@ -160,16 +348,16 @@ impl<'a, W: Write> SerializeMap for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        key.serialize(&mut *self.se)
+        self.se.serialize_map_key(key)
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(&mut *self.se)
     }
@@ -186,14 +374,11 @@ impl<'a, W: Write> SerializeStruct for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.count_field()?;
         if self.with_key {
             key.serialize(&mut *self.se)?;
         }
@@ -201,10 +386,7 @@ impl<'a, W: Write> SerializeStruct for SeqSerializer<'a, W> {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        match self.kind {
-            SeqKind::UnknownLength => self.se.write_end(),
-            SeqKind::KnownLength => Ok(()),
-        }
+        self.end_fields()
     }
 }
 
@@ -212,14 +394,11 @@ impl<'a, W: Write> SerializeStructVariant for SeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        self.count_field()?;
         if self.with_key {
             key.serialize(&mut *self.se)?;
         }
@@ -227,10 +406,7 @@ impl<'a, W: Write> SerializeStructVariant for SeqSerializer<'a, W> {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        match self.kind {
-            SeqKind::UnknownLength => self.se.write_end(),
-            SeqKind::KnownLength => Ok(()),
-        }
+        self.end_fields()
     }
 }
 
@@ -248,7 +424,8 @@ macro_rules! serialize_err {
 }
 
 /// Custom type Serializer for Specific RESP types,
-/// supports: SimpleError, BlobError, SimpleString, BlobString
+/// supports: SimpleError, BlobError, SimpleString, BlobString,
+/// VerbatimString, BigNumber, Raw
 struct RespSpecificSerializer<'a, W: Write> {
     se: &'a mut Serializer<W>,
     resp_kind: &'static str,
@@ -338,21 +515,50 @@ impl<'a, W: Write> serde::Serializer for RespSpecificSerializer<'a, W> {
                 self.se.write_blob_string(v)?;
                 Ok(())
             }
+            VERBATIM_STRING_TOKEN => {
+                check_verbatim(v)?;
+                self.se.write_blob(markers::VERBATIM_STRING, v.as_bytes())?;
+                Ok(())
+            }
+            BIG_NUMBER_TOKEN => {
+                self.se.write_big_number(v)?;
+                Ok(())
+            }
+            RAW_TOKEN => self.serialize_bytes(v.as_bytes()),
             _ => unimplemented!(),
         }
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let s = str::from_utf8(v).map_err(|e| Error::utf8(e.valid_up_to()))?;
+        // blob strings are binary safe
+        if self.resp_kind == BLOB_STRING_TOKEN {
+            return self.se.write_blob(markers::BLOB_STRING, v);
+        }
+        // pre-encoded frames are written as is
+        if self.resp_kind == RAW_TOKEN {
+            return self.se.writer.write_all(v).map_err(Error::io);
+        }
+        let s = str_from_utf8(v)?;
         self.serialize_str(s)
     }
 }
 
-struct PushSerializer<'a, W: Write> {
+/// Serializes a sequence as a push (`>`) or a set (`~`)
+struct MarkedSeqSerializer<'a, W: Write> {
     se: &'a mut Serializer<W>,
+    marker: u8,
+}
+
+impl<'a, W: Write> MarkedSeqSerializer<'a, W> {
+    fn write_len_marker(&mut self, len: usize) -> Result<(), Error> {
+        match self.marker {
+            markers::SET => self.se.write_set_len_marker(len),
+            _ => self.se.write_push_len_marker(len),
+        }
+    }
 }
 
-impl<'a, W: Write> serde::Serializer for PushSerializer<'a, W> {
+impl<'a, W: Write> serde::Serializer for MarkedSeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = SeqSerializer<'a, W>;
@@ -392,17 +598,24 @@ impl<'a, W: Write> serde::Serializer for PushSerializer<'a, W> {
     );
     serialize_err!(serialize_map, Option<usize>: Result<Self::SerializeMap, Self::Error> => Err(Error::unexpected_value("map")));
 
+    /// Sets of unknown length are streamed (`~?\r\n ... .\r\n`), RESP3
+    /// has no streamed push
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        if let Some(len) = len {
-            self.serialize_tuple(len)
-        } else {
-            Err(Error::unexpected_value("unknown len seq"))
+        match len {
+            Some(len) => self.serialize_tuple(len),
+            None if self.marker == markers::SET => {
+                self.se.write_set_nolen_marker()?;
+                Ok(SeqSerializer::unknown_length(self.se).without_key())
+            }
+            None => Err(Error::unexpected_value("unknown len seq")),
         }
     }
 
-    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        self.se.write_push_len_marker(len)?;
-        Ok(SeqSerializer::known_length(self.se).without_key())
+    fn serialize_tuple(mut self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.write_len_marker(len)?;
+        Ok(SeqSerializer::known_length(self.se)
+            .without_key()
+            .with_field_count(len))
     }
 
     fn serialize_tuple_struct(
@@ -424,11 +637,11 @@ impl<'a, W: Write> serde::Serializer for PushSerializer<'a, W> {
     }
 
     fn serialize_struct(
-        self,
+        mut self,
         _name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.se.write_push_len_marker(len)?;
+        self.write_len_marker(len)?;
         Ok(SeqSerializer::known_length(self.se).without_key())
     }
 
@@ -540,9 +753,9 @@ impl<'a, W: Write> SerializeTupleStruct for WithAttributeSeqSerializer<'a, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         match self.state {
             WithAttributeState::Attribute => {
@@ -604,9 +817,6 @@ impl<'a, W: Write> serde::Serializer for AttributeSerializer<'a, W> {
     );
     serialize_err!(serialize_str, &str => Err(Error::unexpected_value("string")));
     serialize_err!(serialize_bytes, &[u8] => Err(Error::unexpected_value("bytes")));
-    serialize_err!(serialize_newtype_struct<T: ?Sized>, &'static str, &T =>
-        Err(Error::unexpected_value("newtype_struct"))
-    );
     serialize_err!(serialize_seq, Option<usize>: Result<Self::SerializeSeq, Self::Error> => Err(Error::unexpected_value("seq")));
     serialize_err!(serialize_tuple, usize: Result<Self::SerializeTuple, Self::Error> =>
         Err(Error::unexpected_value("tuple"))
@@ -620,6 +830,24 @@ impl<'a, W: Write> serde::Serializer for AttributeSerializer<'a, W> {
         Err(Error::unexpected_value("tuple_variant"))
     );
 
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // pre-encoded attribute frame, from WithRawAttribute
+        if name == RAW_TOKEN {
+            return value.serialize(RespSpecificSerializer {
+                se: self.se,
+                resp_kind: RAW_TOKEN,
+            });
+        }
+        Err(Error::unexpected_value("newtype_struct"))
+    }
+
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         if let Some(l) = len {
             self.se.write_attr_len_marker(l)?;
@@ -635,7 +863,7 @@ impl<'a, W: Write> serde::Serializer for AttributeSerializer<'a, W> {
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
         self.se.write_attr_len_marker(len)?;
-        Ok(SeqSerializer::known_length(self.se))
+        Ok(SeqSerializer::known_length(self.se).with_field_count(len))
     }
 
     fn serialize_struct_variant(
@@ -649,8 +877,37 @@ impl<'a, W: Write> serde::Serializer for AttributeSerializer<'a, W> {
     }
 }
 
+/// Simple strings/errors are CRLF terminated, data containing CR or LF
+/// must be sent as blob instead
+fn check_simple(s: &str) -> Result<(), Error> {
+    if has_crlf(s) {
+        return Err(Error::unexpected_value("CRLF in simple string"));
+    }
+
+    Ok(())
+}
+
+/// Verbatim strings start with their format, exactly 3 ASCII characters
+/// then `:`
+fn check_verbatim(s: &str) -> Result<(), Error> {
+    match s.as_bytes() {
+        [f0, f1, f2, b':', ..]
+            if [f0, f1, f2]
+                .iter()
+                .all(|&&c| c.is_ascii_graphic() && c != b':') =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::unexpected_value("verbatim string format")),
+    }
+}
+
+fn has_crlf(s: &str) -> bool {
+    s.bytes().any(|b| b == b'\r' || b == b'\n')
+}
+
 impl<W: Write> Serializer<W> {
-    fn write_i64(&mut self, v: i64) -> Result<(), Error> {
+    pub(crate) fn write_i64(&mut self, v: i64) -> Result<(), Error> {
         write!(self.writer, ":{}\r\n", v).map_err(Error::io)?;
 
         Ok(())
@@ -660,7 +917,7 @@ impl<W: Write> Serializer<W> {
 
         Ok(())
     }
-    fn write_f64(&mut self, v: f64) -> Result<(), Error> {
+    pub(crate) fn write_f64(&mut self, v: f64) -> Result<(), Error> {
         if v.is_nan() {
             return Err(Error::nan());
         }
@@ -675,11 +932,20 @@ impl<W: Write> Serializer<W> {
             return Ok(());
         }
 
-        write!(self.writer, ",{:.}\r\n", v).map_err(Error::io)?;
+        // very large/small magnitudes would otherwise be written with
+        // hundreds of digits
+        let abs = v.abs();
+        if abs >= 1e21 || (abs != 0.0 && abs < 1e-7) {
+            write!(self.writer, ",{:e}\r\n", v).map_err(Error::io)?;
+        } else if self.options.integral_double_fraction && v.fract() == 0.0 {
+            write!(self.writer, ",{:.1}\r\n", v).map_err(Error::io)?;
+        } else {
+            write!(self.writer, ",{:.}\r\n", v).map_err(Error::io)?;
+        }
 
         Ok(())
     }
-    fn write_bool(&mut self, v: bool) -> Result<(), Error> {
+    pub(crate) fn write_bool(&mut self, v: bool) -> Result<(), Error> {
         if v {
             write!(self.writer, "#t\r\n").map_err(Error::io)?;
         } else {
@@ -689,11 +955,18 @@ impl<W: Write> Serializer<W> {
         Ok(())
     }
     fn write_simple_string_char(&mut self, c: char) -> Result<(), Error> {
+        if c == '\r' || c == '\n' {
+            return self.write_simple_string(c.encode_utf8(&mut [0; 4]));
+        }
         write!(self.writer, "+{}\r\n", c).map_err(Error::io)?;
 
         Ok(())
     }
-    fn write_simple_string(&mut self, s: &str) -> Result<(), Error> {
+    pub(crate) fn write_simple_string(&mut self, s: &str) -> Result<(), Error> {
+        if self.options.promote_crlf_to_blob && has_crlf(s) {
+            return self.write_blob_string(s);
+        }
+        check_simple(s)?;
         write!(self.writer, "+{}\r\n", s).map_err(Error::io)?;
 
         Ok(())
@@ -703,7 +976,11 @@ impl<W: Write> Serializer<W> {
 
         Ok(())
     }
-    fn write_simple_error(&mut self, s: &str) -> Result<(), Error> {
+    pub(crate) fn write_simple_error(&mut self, s: &str) -> Result<(), Error> {
+        if self.options.promote_crlf_to_blob && has_crlf(s) {
+            return self.write_blob_error(s);
+        }
+        check_simple(s)?;
         write!(self.writer, "-{}\r\n", s).map_err(Error::io)?;
 
         Ok(())
@@ -713,22 +990,45 @@ impl<W: Write> Serializer<W> {
 
         Ok(())
     }
-    fn write_null(&mut self) -> Result<(), Error> {
+    pub(crate) fn write_blob(&mut self, marker: u8, b: &[u8]) -> Result<(), Error> {
+        write!(self.writer, "{}{}\r\n", marker as char, b.len()).map_err(Error::io)?;
+        self.writer.write_all(b).map_err(Error::io)?;
+        write!(self.writer, "\r\n").map_err(Error::io)?;
+
+        Ok(())
+    }
+    pub(crate) fn write_big_number(&mut self, s: &str) -> Result<(), Error> {
+        check_simple(s)?;
+        write!(self.writer, "({}\r\n", s).map_err(Error::io)?;
+
+        Ok(())
+    }
+    pub(crate) fn write_null(&mut self) -> Result<(), Error> {
         write!(self.writer, "_\r\n").map_err(Error::io)?;
 
         Ok(())
     }
-    fn write_attr_len_marker(&mut self, len: usize) -> Result<(), Error> {
+    pub(crate) fn write_attr_len_marker(&mut self, len: usize) -> Result<(), Error> {
         write!(self.writer, "|{}\r\n", len).map_err(Error::io)?;
 
         Ok(())
     }
-    fn write_push_len_marker(&mut self, len: usize) -> Result<(), Error> {
+    pub(crate) fn write_push_len_marker(&mut self, len: usize) -> Result<(), Error> {
         write!(self.writer, ">{}\r\n", len).map_err(Error::io)?;
 
         Ok(())
     }
-    fn write_array_len_marker(&mut self, len: usize) -> Result<(), Error> {
+    pub(crate) fn write_set_len_marker(&mut self, len: usize) -> Result<(), Error> {
+        write!(self.writer, "~{}\r\n", len).map_err(Error::io)?;
+
+        Ok(())
+    }
+    fn write_set_nolen_marker(&mut self) -> Result<(), Error> {
+        write!(self.writer, "~?\r\n").map_err(Error::io)?;
+
+        Ok(())
+    }
+    pub(crate) fn write_array_len_marker(&mut self, len: usize) -> Result<(), Error> {
         write!(self.writer, "*{}\r\n", len).map_err(Error::io)?;
 
         Ok(())
@@ -738,7 +1038,7 @@ impl<W: Write> Serializer<W> {
 
         Ok(())
     }
-    fn write_map_len_marker(&mut self, len: usize) -> Result<(), Error> {
+    pub(crate) fn write_map_len_marker(&mut self, len: usize) -> Result<(), Error> {
         write!(self.writer, "%{}\r\n", len).map_err(Error::io)?;
 
         Ok(())
@@ -811,25 +1111,31 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        if self.options.string_as_blob {
+            return self.write_blob_string(v.encode_utf8(&mut [0; 4]));
+        }
         self.write_simple_string_char(v)
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if self.options.string_as_blob {
+            return self.write_blob_string(v);
+        }
         self.write_simple_string(v)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let s = str::from_utf8(v).map_err(|e| Error::utf8(e.valid_up_to()))?;
-        self.write_blob_string(s)
+        // blob strings are binary safe
+        self.write_blob(markers::BLOB_STRING, v)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         self.write_null()
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(self)
     }
@@ -849,21 +1155,30 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
+        if self.options.bare_unit_variant {
+            return self.write_simple_string(variant);
+        }
         self.write_map_len_marker(1)?;
         self.write_simple_string(variant)?;
         self.write_null()
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         match name {
-            SIMPLE_ERROR_TOKEN | BLOB_ERROR_TOKEN | SIMPLE_STRING_TOKEN | BLOB_STRING_TOKEN => {
+            SIMPLE_ERROR_TOKEN
+            | BLOB_ERROR_TOKEN
+            | SIMPLE_STRING_TOKEN
+            | BLOB_STRING_TOKEN
+            | VERBATIM_STRING_TOKEN
+            | BIG_NUMBER_TOKEN
+            | RAW_TOKEN => {
                 let se = RespSpecificSerializer {
                     se: self,
                     resp_kind: name,
@@ -871,7 +1186,17 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
                 value.serialize(se)
             }
             PUSH_TOKEN => {
-                let se = PushSerializer { se: self };
+                let se = MarkedSeqSerializer {
+                    se: self,
+                    marker: markers::PUSH,
+                };
+                value.serialize(se)
+            }
+            SET_TOKEN => {
+                let se = MarkedSeqSerializer {
+                    se: self,
+                    marker: markers::SET,
+                };
                 value.serialize(se)
             }
             WITH_ATTRIBUTE_TOKEN => {
@@ -883,7 +1208,7 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     }
 
     /// Serialize as { variant => T }
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -891,7 +1216,7 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         self.write_map_len_marker(1)?;
         self.write_simple_string(variant)?;
@@ -957,7 +1282,7 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
         _name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.serialize_map(Some(len))
+        Ok(self.serialize_map(Some(len))?.with_field_count(len))
     }
 
     /// Serialize as { variant => { struct .. } }
@@ -970,15 +1295,21 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         self.write_map_len_marker(1)?;
         self.write_simple_string(variant)?;
-        self.serialize_map(Some(len))
+        Ok(self.serialize_map(Some(len))?.with_field_count(len))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Deserialize;
 
     use super::*;
+    use crate::types::{
+        owned::{BlobString, SimpleError, SimpleString},
+        Raw, RawRef, Set,
+    };
 
     #[test]
     fn test_serialize_bool() {
@@ -1017,6 +1348,88 @@ mod tests {
         assert_eq!(buf, b",inf\r\n");
     }
 
+    #[test]
+    fn test_serialize_double_scientific() {
+        let cases: [(f64, &[u8]); 10] = [
+            (1e20, b",100000000000000000000\r\n"),
+            (1e21, b",1e21\r\n"),
+            (-1.5e-8, b",-1.5e-8\r\n"),
+            (0.0, b",0\r\n"),
+            (-0.0, b",-0\r\n"),
+            (f64::MIN_POSITIVE, b",2.2250738585072014e-308\r\n"),
+            (1e300, b",1e300\r\n"),
+            (1e-300, b",1e-300\r\n"),
+            (-1e-300, b",-1e-300\r\n"),
+            (5e-324, b",5e-324\r\n"),
+        ];
+        for (v, expected) in cases {
+            let buf = to_vec(&v).unwrap();
+            assert_eq!(buf, expected);
+            let decoded: f64 = crate::from_slice(&buf).unwrap();
+            assert_eq!(decoded.to_bits(), v.to_bits());
+        }
+
+        for v in [f64::MAX, f64::MIN, 1e-7, 0.1, 123456.789] {
+            let buf = to_vec(&v).unwrap();
+            let decoded: f64 = crate::from_slice(&buf).unwrap();
+            assert_eq!(decoded.to_bits(), v.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_serialize_integral_double_fraction() {
+        fn to_vec_fraction(v: f64) -> Vec<u8> {
+            let mut buf = Vec::new();
+            let mut se = Serializer::from_write(&mut buf).integral_double_fraction(true);
+            v.serialize(&mut se).unwrap();
+            buf
+        }
+
+        assert_eq!(to_vec(&10.0f64).unwrap(), b",10\r\n");
+        assert_eq!(to_vec_fraction(10.0), b",10.0\r\n");
+        assert_eq!(to_vec_fraction(-3.0), b",-3.0\r\n");
+        assert_eq!(to_vec_fraction(0.0), b",0.0\r\n");
+        assert_eq!(to_vec_fraction(1.5), b",1.5\r\n");
+        assert_eq!(to_vec_fraction(1e21), b",1e21\r\n");
+        assert_eq!(to_vec_fraction(f64::INFINITY), b",inf\r\n");
+        assert_eq!(
+            crate::from_slice::<_, f64>(&to_vec_fraction(10.0)).unwrap(),
+            10.0
+        );
+    }
+
+    #[test]
+    fn test_serialize_with_options() {
+        #[derive(Serialize)]
+        enum Status {
+            Ok,
+        }
+
+        let options = SerializerOptions::new()
+            .bare_unit_variant(true)
+            .promote_crlf_to_blob(true)
+            .default_string_as_blob(true)
+            .integral_double_fraction(true);
+        let value = (Status::Ok, "key", 2.0f64, SimpleString("a\r\nb".to_owned()));
+        let expected = b"*4\r\n+Ok\r\n$3\r\nkey\r\n,2.0\r\n$4\r\na\r\nb\r\n";
+        assert_eq!(to_vec_with(&value, options).unwrap(), expected);
+
+        let mut buf = Vec::new();
+        let mut se = Serializer::from_write_with(&mut buf, options);
+        assert_eq!(se.options(), options);
+        value.serialize(&mut se).unwrap();
+        assert_eq!(buf, expected);
+
+        let chained = Serializer::from_write(Vec::new())
+            .bare_unit_variant(true)
+            .promote_crlf_to_blob(true)
+            .default_string_as_blob(true)
+            .integral_double_fraction(true);
+        assert_eq!(chained.options(), options);
+
+        assert!(to_vec_with(&value, SerializerOptions::new()).is_err());
+    }
+
     #[test]
     fn test_serialize_char() {
         let chr: char = 'e';
@@ -1031,6 +1444,176 @@ mod tests {
         assert_eq!(buf, b"+hello world\r\n");
     }
 
+    #[test]
+    fn test_serialize_multibyte_lengths() {
+        use crate::types::{owned::BlobError, VerbatimString};
+
+        // "café" is 4 chars but 5 bytes
+        let buf = to_vec(&BlobString::from("café")).unwrap();
+        assert_eq!(buf, "$5\r\ncafé\r\n".as_bytes());
+        let buf = to_vec(&BlobError::from("café")).unwrap();
+        assert_eq!(buf, "!5\r\ncafé\r\n".as_bytes());
+        let buf = to_vec(&VerbatimString::new("txt", "café")).unwrap();
+        assert_eq!(buf, "=9\r\ntxt:café\r\n".as_bytes());
+        let buf = to_vec(&serde_bytes::Bytes::new("café".as_bytes())).unwrap();
+        assert_eq!(buf, "$5\r\ncafé\r\n".as_bytes());
+
+        let value: VerbatimString = crate::from_slice("=9\r\ntxt:café\r\n".as_bytes()).unwrap();
+        assert_eq!(value.text, "café");
+    }
+
+    #[test]
+    fn test_serialize_str_with_crlf() {
+        let str: &str = "hello\r\n+OK";
+        let result = to_vec(&str);
+        assert!(matches!(
+            result,
+            Err(Error::UnexpectedValue("CRLF in simple string"))
+        ));
+
+        let result = to_vec(&'\n');
+        assert!(result.is_err());
+
+        let err = SimpleError::from("ERR\nhello");
+        let result = to_vec(&err);
+        assert!(matches!(
+            result,
+            Err(Error::UnexpectedValue("CRLF in simple string"))
+        ));
+
+        let err = SimpleError::from("ERR hello");
+        let buf = to_vec(&err).unwrap();
+        assert_eq!(buf, b"-ERR hello\r\n");
+
+        let blob = BlobString::from("hello\r\nworld");
+        let buf = to_vec(&blob).unwrap();
+        assert_eq!(buf, b"$12\r\nhello\r\nworld\r\n");
+    }
+
+    #[test]
+    fn test_serialize_promote_crlf_to_blob() {
+        fn to_vec_lossless<S: Serialize>(s: &S) -> Result<Vec<u8>, Error> {
+            let mut buf = Vec::new();
+            let mut se = Serializer::from_write(&mut buf).promote_crlf_to_blob(true);
+            s.serialize(&mut se)?;
+            Ok(buf)
+        }
+
+        // strict by default
+        let result = to_vec(&"hello\nworld");
+        assert!(matches!(
+            result,
+            Err(Error::UnexpectedValue("CRLF in simple string"))
+        ));
+
+        let buf = to_vec_lossless(&"hello\nworld").unwrap();
+        assert_eq!(buf, b"$11\r\nhello\nworld\r\n");
+        let value: String = crate::from_slice(&buf).unwrap();
+        assert_eq!(value, "hello\nworld");
+
+        let buf = to_vec_lossless(&'\n').unwrap();
+        assert_eq!(buf, b"$1\r\n\n\r\n");
+        let buf = to_vec_lossless(&SimpleString::from("a\r\nb")).unwrap();
+        assert_eq!(buf, b"$4\r\na\r\nb\r\n");
+        let buf = to_vec_lossless(&SimpleError::from("ERR\nhello")).unwrap();
+        assert_eq!(buf, b"!9\r\nERR\nhello\r\n");
+
+        // strings without CR/LF are unchanged
+        let buf = to_vec_lossless(&("hello", SimpleError::from("ERR hello"))).unwrap();
+        assert_eq!(buf, b"*2\r\n+hello\r\n-ERR hello\r\n");
+    }
+
+    #[test]
+    fn test_serialize_default_string_as_blob() {
+        #[derive(Serialize)]
+        struct Set<'a>(&'a str, String, char, SimpleString);
+
+        fn to_vec_blob<S: Serialize>(s: &S) -> Vec<u8> {
+            let mut buf = Vec::new();
+            let mut se = Serializer::from_write(&mut buf).default_string_as_blob(true);
+            s.serialize(&mut se).unwrap();
+            buf
+        }
+
+        let value = Set("SET", "my key".into(), '\n', SimpleString::from("ok"));
+        assert_eq!(
+            to_vec_blob(&value),
+            b"*4\r\n$3\r\nSET\r\n$6\r\nmy key\r\n$1\r\n\n\r\n+ok\r\n"
+        );
+
+        // simple strings by default
+        let value = Set("SET", "my key".into(), 'a', SimpleString::from("ok"));
+        assert_eq!(
+            to_vec(&value).unwrap(),
+            b"*4\r\n+SET\r\n+my key\r\n+a\r\n+ok\r\n"
+        );
+    }
+
+    #[test]
+    fn test_serialize_raw() {
+        let cached = Raw(b":1\r\n".to_vec());
+        let value = (RawRef(b"+cached\r\n"), &cached, 2);
+        assert_eq!(to_vec(&value).unwrap(), b"*3\r\n+cached\r\n:1\r\n:2\r\n");
+
+        let value: Vec<u64> = crate::from_slice(&to_vec(&[&cached, &cached]).unwrap()).unwrap();
+        assert_eq!(value, [1, 1]);
+    }
+
+    #[test]
+    fn test_serialize_strict_map_keys() {
+        let options = SerializerOptions::new().strict_map_keys(true);
+
+        let value = BTreeMap::from([(Some("a"), 1), (None, 2)]);
+        assert_eq!(to_vec(&value).unwrap(), b"%2\r\n_\r\n:2\r\n+a\r\n:1\r\n");
+        let err = to_vec_with(&value, options).unwrap_err();
+        assert_eq!(err, Error::unexpected_value("non-scalar map key"));
+
+        let value = BTreeMap::from([(vec![1, 2], 1)]);
+        assert!(to_vec(&value).is_ok());
+        let err = to_vec_with(&value, options).unwrap_err();
+        assert_eq!(err, Error::unexpected_value("non-scalar map key"));
+
+        let value = BTreeMap::from([(1, "a"), (2, "b")]);
+        assert_eq!(
+            to_vec_with(&value, options).unwrap(),
+            b"%2\r\n:1\r\n+a\r\n:2\r\n+b\r\n"
+        );
+        let value = HashMap::from([(BlobString("k".to_owned()), Some(1.5))]);
+        assert_eq!(
+            to_vec_with(&value, options).unwrap(),
+            b"%1\r\n$1\r\nk\r\n,1.5\r\n"
+        );
+
+        let mut buf = Vec::new();
+        let mut se = Serializer::from_write(&mut buf).strict_map_keys(true);
+        assert!(HashMap::from([((), 1)]).serialize(&mut se).is_err());
+    }
+
+    #[test]
+    fn test_serialize_serde_bytes() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Owned {
+            #[serde(with = "serde_bytes")]
+            data: Vec<u8>,
+        }
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Borrowed<'a> {
+            #[serde(with = "serde_bytes")]
+            data: &'a [u8],
+        }
+
+        let value = Owned {
+            data: vec![0xff, 0x00, b'\r', b'\n', 0xc3],
+        };
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(buf, b"%1\r\n+data\r\n$5\r\n\xff\x00\r\n\xc3\r\n");
+        assert_eq!(crate::from_slice::<_, Owned>(&buf).unwrap(), value);
+
+        let borrowed: Borrowed = crate::from_slice(&buf).unwrap();
+        assert_eq!(borrowed.data, value.data);
+        assert_eq!(to_vec(&borrowed).unwrap(), buf);
+    }
+
     #[test]
     fn test_serialize_option() {
         let str: Option<&str> = None;
@@ -1090,6 +1673,74 @@ mod tests {
         assert_eq!(buf, b"*2\r\n:123\r\n+abcd\r\n");
     }
 
+    #[test]
+    fn test_serialize_struct_none_field() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct WithOption {
+            a: Option<usize>,
+            b: usize,
+        }
+
+        // None is a null value, still counted in the map length
+        let value = WithOption { a: None, b: 1 };
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(buf, b"%2\r\n+a\r\n_\r\n+b\r\n:1\r\n");
+        assert_eq!(crate::from_slice::<_, WithOption>(&buf).unwrap(), value);
+
+        let value = WithOption { a: Some(2), b: 1 };
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(buf, b"%2\r\n+a\r\n:2\r\n+b\r\n:1\r\n");
+        assert_eq!(crate::from_slice::<_, WithOption>(&buf).unwrap(), value);
+
+        // serde derive excludes skipped fields from the length it passes
+        #[derive(Serialize)]
+        struct Skipped {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            a: Option<usize>,
+            b: usize,
+        }
+        let buf = to_vec(&Skipped { a: None, b: 1 }).unwrap();
+        assert_eq!(buf, b"%1\r\n+b\r\n:1\r\n");
+    }
+
+    #[test]
+    fn test_serialize_struct_skipped_fields() {
+        #[derive(Serialize)]
+        enum Enum {
+            Struct {
+                #[serde(skip_serializing_if = "Option::is_none")]
+                a: Option<usize>,
+                b: usize,
+            },
+        }
+        let buf = to_vec(&Enum::Struct { a: None, b: 1 }).unwrap();
+        assert_eq!(buf, b"%1\r\n+Struct\r\n%1\r\n+b\r\n:1\r\n");
+
+        // declares 2 fields but serializes only 1
+        struct Undercount;
+        impl Serialize for Undercount {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                let mut st = s.serialize_struct("Undercount", 2)?;
+                st.serialize_field("a", &1)?;
+                st.skip_field("b")?;
+                st.end()
+            }
+        }
+        assert!(to_vec(&Undercount).is_err());
+
+        // declares 1 field but serializes 2
+        struct Overcount;
+        impl Serialize for Overcount {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                let mut st = s.serialize_struct("Overcount", 1)?;
+                st.serialize_field("a", &1)?;
+                st.serialize_field("b", &2)?;
+                st.end()
+            }
+        }
+        assert!(to_vec(&Overcount).is_err());
+    }
+
     #[test]
     fn test_serialize_map() {
         let mut map = BTreeMap::new();
@@ -1099,6 +1750,112 @@ mod tests {
         assert_eq!(buf, b"%2\r\n+a\r\n+b\r\n+c\r\n+d\r\n");
     }
 
+    #[test]
+    fn test_serialize_map_unknown_length() {
+        // filtered iterators have no exact size_hint, so serde passes no length
+        struct Evens<'a>(&'a BTreeMap<&'a str, u64>);
+        impl Serialize for Evens<'_> {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_map(self.0.iter().filter(|(_, v)| **v % 2 == 0))
+            }
+        }
+
+        let map = BTreeMap::from([("a", 1), ("b", 2), ("c", 4)]);
+        assert_eq!(
+            map.iter().filter(|(_, v)| **v % 2 == 0).size_hint(),
+            (0, Some(3))
+        );
+        let buf = to_vec(&Evens(&map)).unwrap();
+        assert_eq!(buf, b"%?\r\n+b\r\n:2\r\n+c\r\n:4\r\n.\r\n");
+
+        // nothing left after filtering
+        let odd = BTreeMap::from([("a", 1)]);
+        let buf = to_vec(&(Evens(&odd), 1)).unwrap();
+        assert_eq!(buf, b"*2\r\n%?\r\n.\r\n:1\r\n");
+
+        // streamed maps read back
+        let map = HashMap::from([("a".to_string(), 2), ("b".to_string(), 4)]);
+        let evens: BTreeMap<&str, u64> = map.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        let buf = to_vec(&(Evens(&evens), Evens(&odd))).unwrap();
+        let value: (HashMap<String, u64>, HashMap<String, u64>) = crate::from_slice(&buf).unwrap();
+        assert_eq!(value, (map, HashMap::new()));
+    }
+
+    #[test]
+    fn test_serialize_set() {
+        use std::collections::BTreeSet;
+
+        let buf = to_vec(&Set(vec![1, 2])).unwrap();
+        assert_eq!(buf, b"~2\r\n:1\r\n:2\r\n");
+        let buf = to_vec(&Set(BTreeSet::from(["a"]))).unwrap();
+        assert_eq!(buf, b"~1\r\n+a\r\n");
+
+        struct Evens<'a>(&'a [u64]);
+        impl Serialize for Evens<'_> {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_seq(self.0.iter().filter(|v| **v % 2 == 0))
+            }
+        }
+
+        let buf = to_vec(&Set(Evens(&[1, 2, 4]))).unwrap();
+        assert_eq!(buf, b"~?\r\n:2\r\n:4\r\n.\r\n");
+        let buf = to_vec(&Evens(&[1, 2, 4])).unwrap();
+        assert_eq!(buf, b"*?\r\n:2\r\n:4\r\n.\r\n");
+        let buf = to_vec(&(Set(Evens(&[1])), 1)).unwrap();
+        assert_eq!(buf, b"*2\r\n~?\r\n.\r\n:1\r\n");
+
+        let value: Set<Vec<u64>> = crate::from_slice(b"~2\r\n:1\r\n:2\r\n").unwrap();
+        assert_eq!(to_vec(&value).unwrap(), b"~2\r\n:1\r\n:2\r\n");
+
+        // streamed sets and arrays read back
+        let buf = to_vec(&Set(Evens(&[1, 2, 4]))).unwrap();
+        let value: Set<Vec<u64>> = crate::from_slice(&buf).unwrap();
+        assert_eq!(value.0, [2, 4]);
+        let value: crate::Value = crate::from_slice(&buf).unwrap();
+        let elements = vec![crate::Value::Integer(2), crate::Value::Integer(4)];
+        assert_eq!(value, crate::Value::Set(elements.clone()));
+        let buf = to_vec(&Evens(&[1, 2, 4])).unwrap();
+        let value: crate::Value = crate::from_slice(&buf).unwrap();
+        assert_eq!(value, crate::Value::Array(elements));
+        let buf = to_vec(&(Set(Evens(&[1])), 1)).unwrap();
+        let value: (Vec<u64>, u64) = crate::from_slice(&buf).unwrap();
+        assert_eq!(value, (vec![], 1));
+
+        // tuple elements must match the length written in the header
+        struct Tuple(usize, usize);
+        impl Serialize for Tuple {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                let mut tuple = s.serialize_tuple(self.0)?;
+                for i in 0..self.1 {
+                    tuple.serialize_element(&i)?;
+                }
+                tuple.end()
+            }
+        }
+        assert_eq!(to_vec(&Set(Tuple(2, 2))).unwrap(), b"~2\r\n:0\r\n:1\r\n");
+        assert!(to_vec(&Set(Tuple(2, 1))).is_err());
+        assert!(to_vec(&Set(Tuple(1, 2))).is_err());
+    }
+
+    #[test]
+    fn test_serialize_map_typed_keys() {
+        let mut map = HashMap::new();
+        map.insert(BlobString::from("a"), 1);
+        let buf = to_vec(&map).unwrap();
+        assert_eq!(buf, b"%1\r\n$1\r\na\r\n:1\r\n");
+
+        let mut map = HashMap::new();
+        map.insert(SimpleString::from("a"), 1);
+        let buf = to_vec(&map).unwrap();
+        assert_eq!(buf, b"%1\r\n+a\r\n:1\r\n");
+    }
+
     #[test]
     fn test_serialize_seq() {
         let seq = vec!["a", "b", "c", "d"];
@@ -1110,6 +1867,26 @@ mod tests {
         assert_eq!(buf, b"*4\r\n:1\r\n:3\r\n+abc\r\n,10.5\r\n");
     }
 
+    #[test]
+    fn test_serialize_empty_aggregates() {
+        let buf = to_vec(&Vec::<u64>::new()).unwrap();
+        assert_eq!(buf, b"*0\r\n");
+        let value: Vec<u64> = crate::from_slice(&buf).unwrap();
+        assert!(value.is_empty());
+
+        let buf = to_vec(&HashMap::<String, u64>::new()).unwrap();
+        assert_eq!(buf, b"%0\r\n");
+        let value: HashMap<String, u64> = crate::from_slice(&buf).unwrap();
+        assert!(value.is_empty());
+
+        // the next element starts right after the empty aggregate
+        let value = (Vec::<u64>::new(), HashMap::<String, u64>::new(), 1);
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(buf, b"*3\r\n*0\r\n%0\r\n:1\r\n");
+        let decoded: (Vec<u64>, HashMap<String, u64>, u64) = crate::from_slice(&buf).unwrap();
+        assert_eq!(decoded, value);
+    }
+
     #[test]
     fn test_serialize_enum() {
         #[derive(Serialize)]
@@ -1137,4 +1914,63 @@ mod tests {
         let buf = to_vec(&unit_variant).unwrap();
         assert_eq!(buf, b"%1\r\n+Unit\r\n_\r\n");
     }
+
+    #[test]
+    fn test_enum_round_trip() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum E {
+            A(u64),
+            B(String),
+            C(Option<u64>),
+            D(Vec<i64>),
+            Tuple(u64, String),
+            Struct { a: u64 },
+            Unit,
+        }
+
+        let buf = to_vec(&E::A(5)).unwrap();
+        assert_eq!(buf, b"%1\r\n+A\r\n:5\r\n");
+        let value: E = crate::from_slice(&buf).unwrap();
+        assert_eq!(value, E::A(5));
+
+        // variant name sent as blob string
+        let value: E = crate::from_slice(b"%1\r\n$1\r\nB\r\n$2\r\nhi\r\n").unwrap();
+        assert_eq!(value, E::B("hi".into()));
+
+        for value in [
+            E::B("hi".into()),
+            E::C(None),
+            E::C(Some(1)),
+            E::D(vec![-1, 2]),
+            E::Tuple(1, "a".into()),
+            E::Struct { a: 1 },
+            E::Unit,
+        ] {
+            let buf = to_vec(&value).unwrap();
+            let result: E = crate::from_slice(&buf).unwrap();
+            assert_eq!(result, value);
+        }
+    }
+
+    #[test]
+    fn test_serialize_bare_unit_variant() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum Enum {
+            Tuple(usize),
+            Unit,
+        }
+
+        let mut buf = Vec::new();
+        let mut se = Serializer::from_write(&mut buf).bare_unit_variant(true);
+        Enum::Unit.serialize(&mut se).unwrap();
+        Enum::Tuple(1).serialize(&mut se).unwrap();
+        assert_eq!(buf, b"+Unit\r\n%1\r\n+Tuple\r\n:1\r\n");
+
+        let value: Enum = crate::from_slice(b"+Unit\r\n").unwrap();
+        assert_eq!(value, Enum::Unit);
+
+        let buf = to_vec(&Enum::Unit).unwrap();
+        let value: Enum = crate::from_slice(&buf).unwrap();
+        assert_eq!(value, Enum::Unit);
+    }
 }
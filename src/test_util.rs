@@ -0,0 +1,83 @@
+//! Helpers for downstream crates to check their types against this crate.
+
+use std::fmt::Debug;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{to_vec, Deserializer};
+
+/// Serializes `value` with [`to_vec`](crate::to_vec), deserializes it back
+/// with [`from_slice`](crate::from_slice) and asserts the result equals
+/// `value` and all bytes were consumed.
+///
+/// # Panics
+///
+/// Panics if either step fails or the deserialized value differs.
+///
+/// ```rust
+/// #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+/// struct Reply {
+///     id: u64,
+///     name: Option<String>,
+/// }
+///
+/// deseresp::test_util::round_trip(&Reply { id: 1, name: None });
+/// ```
+pub fn round_trip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let buf = to_vec(value).expect("serialize");
+    let mut d = Deserializer::from_slice(&buf);
+    let result = T::deserialize(&mut d).expect("deserialize");
+    d.expect_eof().expect("serialized bytes left unread");
+    assert_eq!(
+        &result,
+        value,
+        "round trip of {:?}",
+        String::from_utf8_lossy(&buf)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Kind {
+        A,
+        B(u64),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Reply {
+        id: i64,
+        score: f64,
+        tags: Vec<Option<String>>,
+        fields: BTreeMap<String, Kind>,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&42u64);
+        round_trip(&-1.5f64);
+        round_trip(&vec![Some("a".to_owned()), None]);
+        round_trip(&Reply {
+            id: -1,
+            score: 0.5,
+            tags: vec![Some("t".into()), None],
+            fields: BTreeMap::from([("a".into(), Kind::A), ("b".into(), Kind::B(2))]),
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "round trip")]
+    fn test_round_trip_lossy() {
+        // Some(None) and None are both written as null
+        round_trip(&Some(None::<u64>));
+    }
+}
@@ -0,0 +1,257 @@
+//! Durations read from integer replies, such as `TTL` (seconds) and
+//! `PTTL` (milliseconds), and the clock of the `TIME` reply.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use deseresp::time::{SecondsDuration, Ttl};
+//!
+//! let ttl: Ttl<SecondsDuration> = deseresp::from_slice(b":60\r\n").unwrap();
+//! assert_eq!(ttl, Ttl::Expire(SecondsDuration(Duration::from_secs(60))));
+//!
+//! let ttl: Ttl<SecondsDuration> = deseresp::from_slice(b":-2\r\n").unwrap();
+//! assert_eq!(ttl.into_option(), None);
+//! ```
+
+use std::{
+    borrow::Cow,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{
+    de::{self, IntoDeserializer},
+    ser, Deserialize, Serialize,
+};
+
+use crate::types::owned::BlobString;
+
+/// Duration from an integer number of seconds, as returned by `TTL`
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct SecondsDuration(pub Duration);
+
+/// Duration from an integer number of milliseconds, as returned by `PTTL`
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct MillisDuration(pub Duration);
+
+impl<'de> Deserialize<'de> for SecondsDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(|secs| SecondsDuration(Duration::from_secs(secs)))
+    }
+}
+
+impl Serialize for SecondsDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.0.as_secs())
+    }
+}
+
+impl<'de> Deserialize<'de> for MillisDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(|millis| MillisDuration(Duration::from_millis(millis)))
+    }
+}
+
+impl Serialize for MillisDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let millis = u64::try_from(self.0.as_millis())
+            .map_err(|_| ser::Error::custom("duration overflows u64 milliseconds"))?;
+        serializer.serialize_u64(millis)
+    }
+}
+
+/// Reply of `TTL`/`PTTL` and the like, telling apart the `-1` (key exists
+/// without an expire) and `-2` (key doesn't exist) sentinels from the
+/// remaining duration `D`
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Ttl<D> {
+    /// Remaining time to live
+    Expire(D),
+    /// `-1`, the key has no associated expire
+    NoExpire,
+    /// `-2`, the key doesn't exist
+    NoKey,
+}
+
+impl<D> Ttl<D> {
+    /// Remaining time to live, `None` for either sentinel
+    pub fn into_option(self) -> Option<D> {
+        match self {
+            Ttl::Expire(d) => Some(d),
+            Ttl::NoExpire | Ttl::NoKey => None,
+        }
+    }
+}
+
+impl<D> From<Ttl<D>> for Option<D> {
+    fn from(ttl: Ttl<D>) -> Self {
+        ttl.into_option()
+    }
+}
+
+impl<'de, D: Deserialize<'de>> Deserialize<'de> for Ttl<D> {
+    fn deserialize<De>(deserializer: De) -> Result<Self, De::Error>
+    where
+        De: serde::Deserializer<'de>,
+    {
+        match i64::deserialize(deserializer)? {
+            -1 => Ok(Ttl::NoExpire),
+            -2 => Ok(Ttl::NoKey),
+            n => {
+                let de: de::value::I64Deserializer<De::Error> = n.into_deserializer();
+                D::deserialize(de).map(Ttl::Expire)
+            }
+        }
+    }
+}
+
+impl<D: Serialize> Serialize for Ttl<D> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Ttl::Expire(d) => d.serialize(serializer),
+            Ttl::NoExpire => serializer.serialize_i64(-1),
+            Ttl::NoKey => serializer.serialize_i64(-2),
+        }
+    }
+}
+
+/// Server clock from the `TIME` reply, an array of the unix time in
+/// seconds and the microseconds elapsed in the current second, both as
+/// blob strings: `*2\r\n$10\r\n1714000000\r\n$6\r\n123456\r\n`
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct ServerTime(pub SystemTime);
+
+impl ServerTime {
+    /// Time elapsed since the unix epoch
+    pub fn since_epoch(&self) -> Duration {
+        self.0.duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+impl<'de> Deserialize<'de> for ServerTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (secs, micros): (Cow<str>, Cow<str>) = Deserialize::deserialize(deserializer)?;
+        let secs: u64 = secs
+            .parse()
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&secs), &"seconds"))?;
+        let micros: u32 = micros
+            .parse()
+            .ok()
+            .filter(|&m| m < 1_000_000)
+            .ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Str(&micros), &"microseconds")
+            })?;
+        let since_epoch = Duration::new(secs, micros * 1000);
+        UNIX_EPOCH
+            .checked_add(since_epoch)
+            .map(ServerTime)
+            .ok_or_else(|| de::Error::custom("time out of range"))
+    }
+}
+
+impl Serialize for ServerTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let since_epoch = self
+            .0
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ser::Error::custom("time before unix epoch"))?;
+        let secs = BlobString(since_epoch.as_secs().to_string());
+        let micros = BlobString(since_epoch.subsec_micros().to_string());
+        (secs, micros).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        test_utils::{test_deserialize, test_deserialize_result},
+        to_vec, Error,
+    };
+
+    #[test]
+    fn test_deserialize_duration() {
+        test_deserialize(b":90\r\n", |value: SecondsDuration| {
+            assert_eq!(value.0, Duration::from_secs(90));
+        });
+        test_deserialize(b":1500\r\n", |value: MillisDuration| {
+            assert_eq!(value.0, Duration::from_millis(1500));
+        });
+        test_deserialize_result(b":-1\r\n", |value: Result<SecondsDuration, Error>| {
+            assert!(value.is_err());
+        });
+    }
+
+    #[test]
+    fn test_deserialize_ttl() {
+        test_deserialize(b":90\r\n", |value: Ttl<SecondsDuration>| {
+            assert_eq!(value, Ttl::Expire(SecondsDuration(Duration::from_secs(90))));
+        });
+        test_deserialize(b":-1\r\n", |value: Ttl<MillisDuration>| {
+            assert_eq!(value, Ttl::NoExpire);
+            assert_eq!(value.into_option(), None);
+        });
+        test_deserialize(b":-2\r\n", |value: Ttl<MillisDuration>| {
+            assert_eq!(value, Ttl::NoKey);
+            assert_eq!(Option::from(value), None::<MillisDuration>);
+        });
+        test_deserialize_result(b":-3\r\n", |value: Result<Ttl<SecondsDuration>, Error>| {
+            assert!(matches!(value, Err(Error::Custom(_))), "{:?}", value);
+        });
+    }
+
+    #[test]
+    fn test_serialize_ttl() {
+        let ttl = Ttl::Expire(MillisDuration(Duration::from_millis(1500)));
+        assert_eq!(to_vec(&ttl).unwrap(), b":1500\r\n");
+        assert_eq!(
+            to_vec(&Ttl::<SecondsDuration>::NoExpire).unwrap(),
+            b":-1\r\n"
+        );
+        assert_eq!(to_vec(&Ttl::<SecondsDuration>::NoKey).unwrap(), b":-2\r\n");
+        let ttl = SecondsDuration(Duration::from_millis(2500));
+        assert_eq!(to_vec(&ttl).unwrap(), b":2\r\n");
+    }
+
+    #[test]
+    fn test_server_time() {
+        let input = b"*2\r\n$10\r\n1714000000\r\n$6\r\n123456\r\n";
+        test_deserialize(input, |value: ServerTime| {
+            assert_eq!(value.since_epoch(), Duration::new(1714000000, 123456000));
+            assert_eq!(to_vec(&value).unwrap(), input);
+        });
+
+        let inputs: [&[u8]; 3] = [
+            b"*2\r\n$10\r\n1714000000\r\n$7\r\n1000000\r\n",
+            b"*2\r\n$2\r\n-1\r\n$1\r\n0\r\n",
+            b"*2\r\n:1714000000\r\n:0\r\n",
+        ];
+        for input in inputs {
+            test_deserialize_result(input, |value: Result<ServerTime, Error>| {
+                assert!(value.is_err());
+            });
+        }
+    }
+}
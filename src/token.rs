@@ -0,0 +1,397 @@
+use std::io::Write;
+
+use crate::{
+    de::{Reader, RefReader},
+    error::str_from_utf8,
+    markers, Error, Result, Serializer,
+};
+
+/// A flattened RESP event, aggregates are represented by a start token
+/// carrying their length, followed by their elements and an [`Token::End`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// `*<len>`, followed by len values then End
+    ArrayStart(usize),
+    /// `~<len>`, followed by len values then End
+    SetStart(usize),
+    /// `><len>`, followed by len values then End
+    PushStart(usize),
+    /// `%<len>`, followed by len key/value pairs then End
+    MapStart(usize),
+    /// `|<len>`, followed by len key/value pairs then End, the attribute
+    /// itself is not counted as an element of the enclosing aggregate
+    AttributeStart(usize),
+    /// End of the latest started aggregate
+    End,
+    /// `+<string>`
+    SimpleString(String),
+    /// `-<string>`
+    SimpleError(String),
+    /// `$<len>`
+    BlobString(Vec<u8>),
+    /// `!<len>`
+    BlobError(Vec<u8>),
+    /// `=<len>`, including the format prefix
+    VerbatimString(Vec<u8>),
+    /// `:<number>`
+    Integer(i64),
+    /// `,<double>`
+    Double(f64),
+    /// `#t` or `#f`
+    Boolean(bool),
+    /// `(<big number>`
+    BigNumber(String),
+    /// `_`
+    Null,
+}
+
+/// Tokenizes all RESP values in the input into a flat stream of [`Token`]s
+/// without building intermediate values.
+///
+/// Streamed strings and aggregates (`$?`, `*?`, `%?`...) have no [`Token`]
+/// with a length to start them and are rejected.
+pub fn tokenize(input: &[u8]) -> Result<Vec<Token>> {
+    let mut reader = RefReader::from_slice(input);
+    let mut tokens = Vec::new();
+    // remaining elements of each opening aggregate, and whether it's an attribute
+    let mut stack: Vec<(usize, bool)> = Vec::new();
+
+    if reader.peek_u8()?.is_none() {
+        return Ok(tokens);
+    }
+
+    loop {
+        let (token, len) = read_token(&mut reader)?;
+        let is_attribute = matches!(token, Token::AttributeStart(_));
+        tokens.push(token);
+
+        match len {
+            Some(len) if len > 0 => {
+                stack.push((len, is_attribute));
+                continue;
+            }
+            Some(_) => tokens.push(Token::End),
+            None => {}
+        }
+
+        // a complete value (or attribute) was read, close finished aggregates
+        let mut is_value = !is_attribute;
+        while is_value {
+            match stack.last_mut() {
+                Some((remaining, attr)) => {
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        break;
+                    }
+                    is_value = !*attr;
+                    stack.pop();
+                    tokens.push(Token::End);
+                }
+                None => break,
+            }
+        }
+
+        if stack.is_empty() && is_value && reader.peek_u8()?.is_none() {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Writes a flat stream of [`Token`]s back to RESP bytes.
+///
+/// The stream is validated to be balanced: every aggregate start is followed by
+/// exactly its number of values then an [`Token::End`], and every attribute by
+/// the value it applies to. On error, the tokens before the imbalance may have
+/// already been written.
+pub fn write_tokens<W: Write>(w: W, tokens: &[Token]) -> Result<()> {
+    let mut se = Serializer::from_write(w);
+    // remaining elements of each opening aggregate, and whether it's an attribute
+    let mut stack: Vec<(usize, bool)> = Vec::new();
+    // an attribute was closed, the value it applies to must follow
+    let mut attribute_pending = false;
+
+    for token in tokens {
+        let mut is_value = true;
+        if *token == Token::End {
+            if attribute_pending {
+                return Err(Error::unexpected_value("attribute without value"));
+            }
+        } else {
+            attribute_pending = false;
+        }
+        match token {
+            Token::ArrayStart(len) => {
+                se.write_array_len_marker(*len)?;
+                stack.push((*len, false));
+                continue;
+            }
+            Token::SetStart(len) => {
+                se.write_set_len_marker(*len)?;
+                stack.push((*len, false));
+                continue;
+            }
+            Token::PushStart(len) => {
+                se.write_push_len_marker(*len)?;
+                stack.push((*len, false));
+                continue;
+            }
+            Token::MapStart(len) => {
+                se.write_map_len_marker(*len)?;
+                stack.push((len.checked_mul(2).ok_or_else(Error::overflow)?, false));
+                continue;
+            }
+            Token::AttributeStart(len) => {
+                se.write_attr_len_marker(*len)?;
+                stack.push((len.checked_mul(2).ok_or_else(Error::overflow)?, true));
+                continue;
+            }
+            Token::End => match stack.pop() {
+                Some((0, attr)) => {
+                    is_value = !attr;
+                    attribute_pending = attr;
+                }
+                Some(_) => return Err(Error::unexpected_value("end before all elements")),
+                None => return Err(Error::unexpected_value("end without start")),
+            },
+            Token::SimpleString(s) => se.write_simple_string(s)?,
+            Token::SimpleError(s) => se.write_simple_error(s)?,
+            Token::BlobString(b) => se.write_blob(markers::BLOB_STRING, b)?,
+            Token::BlobError(b) => se.write_blob(markers::BLOB_ERROR, b)?,
+            Token::VerbatimString(b) => se.write_blob(markers::VERBATIM_STRING, b)?,
+            Token::Integer(i) => se.write_i64(*i)?,
+            Token::Double(d) => se.write_f64(*d)?,
+            Token::Boolean(b) => se.write_bool(*b)?,
+            Token::BigNumber(n) => se.write_big_number(n)?,
+            Token::Null => se.write_null()?,
+        }
+
+        if is_value {
+            if let Some((remaining, _)) = stack.last_mut() {
+                if *remaining == 0 {
+                    return Err(Error::unexpected_value("elements exceed length"));
+                }
+                *remaining -= 1;
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(Error::unexpected_value("aggregate without end"));
+    }
+    if attribute_pending {
+        return Err(Error::unexpected_value("attribute without value"));
+    }
+
+    Ok(())
+}
+
+/// Reads a single token, returns number of elements to follow
+/// if the token starts an aggregate
+fn read_token<'de, R: Reader<'de>>(reader: &mut R) -> Result<(Token, Option<usize>)> {
+    let marker = reader.read_u8()?.ok_or_else(Error::eof)?;
+    let streamable = matches!(
+        marker,
+        markers::ARRAY | markers::SET | markers::PUSH | markers::MAP | markers::BLOB_STRING
+    );
+    if streamable && reader.peek_u8()? == Some(b'?') {
+        return Err(Error::unexpected_value("streamed value"));
+    }
+    let token = match marker {
+        markers::ARRAY | markers::SET | markers::PUSH | markers::MAP | markers::ATTRIBUTE => {
+            let len = reader.read_length()?;
+            reader.read_crlf()?;
+            let pairs = len.checked_mul(2).ok_or_else(Error::overflow);
+            return Ok(match marker {
+                markers::ARRAY => (Token::ArrayStart(len), Some(len)),
+                markers::SET => (Token::SetStart(len), Some(len)),
+                markers::PUSH => (Token::PushStart(len), Some(len)),
+                markers::MAP => (Token::MapStart(len), Some(pairs?)),
+                _ => (Token::AttributeStart(len), Some(pairs?)),
+            });
+        }
+        markers::SIMPLE_STRING => Token::SimpleString(read_line(reader)?),
+        markers::SIMPLE_ERROR => Token::SimpleError(read_line(reader)?),
+        markers::BIG_NUMBER => Token::BigNumber(read_line(reader)?),
+        markers::BLOB_STRING => Token::BlobString(read_blob(reader)?),
+        markers::BLOB_ERROR => Token::BlobError(read_blob(reader)?),
+        markers::VERBATIM_STRING => Token::VerbatimString(read_blob(reader)?),
+        markers::INTEGER => {
+            let num = match reader.peek_u8()? {
+                Some(b'-') => {
+                    reader.read_u8()?;
+                    let num: u64 = reader.read_unsigned()?;
+                    0i64.checked_sub_unsigned(num).ok_or_else(Error::overflow)?
+                }
+                _ => reader.read_unsigned::<i64>()?,
+            };
+            reader.read_crlf()?;
+            Token::Integer(num)
+        }
+        markers::DOUBLE => {
+            let num = reader.read_double()?;
+            reader.read_crlf()?;
+            Token::Double(num)
+        }
+        markers::BOOLEAN => Token::Boolean(reader.read_bool()?),
+        markers::NULL => {
+            reader.read_crlf()?;
+            Token::Null
+        }
+        _ => return Err(Error::expected_value("type header")),
+    };
+
+    Ok((token, None))
+}
+
+fn read_line<'de, R: Reader<'de>>(reader: &mut R) -> Result<String> {
+    let bytes = reader.read_slice_until(|ch| ch == b'\r' || ch == b'\n', true)?;
+    let s = str_from_utf8(&bytes)?;
+
+    Ok(s.to_owned())
+}
+
+fn read_blob<'de, R: Reader<'de>>(reader: &mut R) -> Result<Vec<u8>> {
+    let len = reader.read_length()?;
+    reader.read_crlf()?;
+    let bytes = reader.read_slice(len, true)?;
+
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_scalar() {
+        let tokens = tokenize(b":-42\r\n").unwrap();
+        assert_eq!(tokens, [Token::Integer(-42)]);
+
+        let tokens = tokenize(b"+OK\r\n$5\r\nhello\r\n").unwrap();
+        assert_eq!(
+            tokens,
+            [
+                Token::SimpleString("OK".into()),
+                Token::BlobString(b"hello".to_vec())
+            ]
+        );
+
+        assert!(tokenize(b"$5\r\nhel").is_err());
+
+        let tokens = tokenize(b":-9223372036854775808\r\n:9223372036854775807\r\n").unwrap();
+        assert_eq!(tokens, [Token::Integer(i64::MIN), Token::Integer(i64::MAX)]);
+        assert_eq!(
+            tokenize(b":-9223372036854775809\r\n").unwrap_err(),
+            Error::Parse
+        );
+
+        for input in [&b"*?\r\n.\r\n"[..], b"%?\r\n.\r\n", b"$?\r\n;0\r\n"] {
+            let err = tokenize(input).unwrap_err();
+            assert_eq!(err, Error::UnexpectedValue("streamed value"));
+        }
+        assert_eq!(
+            tokenize(b"+?\r\n").unwrap(),
+            [Token::SimpleString("?".into())]
+        );
+    }
+
+    #[test]
+    fn test_write_tokens_round_trip() {
+        let input =
+            b"|1\r\n+ttl\r\n:3600\r\n*3\r\n:1\r\n%1\r\n+a\r\n~2\r\n#t\r\n_\r\n*0\r\n,1.5\r\n";
+        let tokens = tokenize(input).unwrap();
+        let mut buf = Vec::new();
+        write_tokens(&mut buf, &tokens).unwrap();
+        assert_eq!(buf, input);
+
+        let input =
+            b">2\r\n$3\r\n\x00\xff\x01\r\n(12345678901234567890\r\n=8\r\ntxt:some\r\n!3\r\nERR\r\n";
+        let tokens = tokenize(input).unwrap();
+        let mut buf = Vec::new();
+        write_tokens(&mut buf, &tokens).unwrap();
+        assert_eq!(buf, input);
+    }
+
+    #[test]
+    fn test_write_tokens_unbalanced() {
+        let mut buf = Vec::new();
+        let result = write_tokens(&mut buf, &[Token::ArrayStart(2), Token::Null, Token::End]);
+        assert!(result.is_err());
+
+        let result = write_tokens(&mut buf, &[Token::ArrayStart(1), Token::Null, Token::Null]);
+        assert!(result.is_err());
+
+        let result = write_tokens(&mut buf, &[Token::ArrayStart(1), Token::Null]);
+        assert!(result.is_err());
+
+        let result = write_tokens(&mut buf, &[Token::Null, Token::End]);
+        assert!(result.is_err());
+
+        let tokens = [
+            Token::MapStart(1),
+            Token::SimpleString("a".into()),
+            Token::AttributeStart(0),
+            Token::End,
+            Token::Integer(1),
+            Token::End,
+        ];
+        let mut buf = Vec::new();
+        write_tokens(&mut buf, &tokens).unwrap();
+        assert_eq!(buf, b"%1\r\n+a\r\n|0\r\n:1\r\n");
+
+        // an attribute must be followed by the value it applies to
+        let err = write_tokens(&mut buf, &[Token::AttributeStart(0), Token::End]).unwrap_err();
+        assert_eq!(err, Error::UnexpectedValue("attribute without value"));
+
+        let tokens = [
+            Token::ArrayStart(1),
+            Token::Null,
+            Token::AttributeStart(0),
+            Token::End,
+            Token::End,
+        ];
+        let err = write_tokens(&mut buf, &tokens).unwrap_err();
+        assert_eq!(err, Error::UnexpectedValue("attribute without value"));
+
+        let tokens = [
+            Token::AttributeStart(0),
+            Token::End,
+            Token::AttributeStart(0),
+            Token::End,
+            Token::ArrayStart(0),
+            Token::End,
+        ];
+        let mut buf = Vec::new();
+        write_tokens(&mut buf, &tokens).unwrap();
+        assert_eq!(buf, b"|0\r\n|0\r\n*0\r\n");
+    }
+
+    #[test]
+    fn test_tokenize_nested() {
+        let input =
+            b"|1\r\n+ttl\r\n:3600\r\n*3\r\n:1\r\n%1\r\n+a\r\n~2\r\n#t\r\n_\r\n*0\r\n,1.5\r\n";
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(
+            tokens,
+            [
+                Token::AttributeStart(1),
+                Token::SimpleString("ttl".into()),
+                Token::Integer(3600),
+                Token::End,
+                Token::ArrayStart(3),
+                Token::Integer(1),
+                Token::MapStart(1),
+                Token::SimpleString("a".into()),
+                Token::SetStart(2),
+                Token::Boolean(true),
+                Token::Null,
+                Token::End,
+                Token::End,
+                Token::ArrayStart(0),
+                Token::End,
+                Token::End,
+                Token::Double(1.5),
+            ]
+        );
+    }
+}
@@ -5,11 +5,19 @@ pub(crate) const SIMPLE_ERROR_TOKEN: &str = "$SimpleError";
 pub(crate) const BLOB_ERROR_TOKEN: &str = "$BulkError";
 pub(crate) const SIMPLE_STRING_TOKEN: &str = "$SimpleString";
 pub(crate) const BLOB_STRING_TOKEN: &str = "$BulkString";
-pub(crate) const ATTRIBUTE_SKIP_TOKEN: &str = "$AttributeSkip";
 pub(crate) const WITH_ATTRIBUTE_TOKEN: &str = "$WithAttribute";
+pub(crate) const WITH_RAW_ATTRIBUTE_TOKEN: &str = "$WithRawAttribute";
 pub(crate) const PUSH_TOKEN: &str = "$Push";
 pub(crate) const VALUE_TOKEN: &str = "$Value";
 pub(crate) const PUSH_OR_VALUE_TOKEN: &str = "$PushOrValue";
+pub(crate) const RESP_VALUE_TOKEN: &str = "$RespValue";
+// as RESP_VALUE_TOKEN, pushes included
+pub(crate) const RESP_FRAME_TOKEN: &str = "$RespFrame";
+pub(crate) const BOUNDED_STRING_TOKEN: &str = "$BoundedString";
+pub(crate) const SET_TOKEN: &str = "$Set";
+pub(crate) const VERBATIM_STRING_TOKEN: &str = "$VerbatimString";
+pub(crate) const BIG_NUMBER_TOKEN: &str = "$BigNumber";
+pub(crate) const RAW_TOKEN: &str = "$Raw";
 
 use std::marker::PhantomData;
 
@@ -18,6 +26,8 @@ use serde::{
     ser::SerializeTupleStruct,
     Deserialize, Serialize,
 };
+
+use crate::value::{split_verbatim, Bytes, Kind};
 pub mod owned {
     //! Contain owned types (String, Vec)
     use serde::{de::Visitor, Serialize};
@@ -26,19 +36,19 @@ pub mod owned {
 
     /// Expects a SimpleError from deserializer,
     /// Serialize as a RESP SimpleError
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct SimpleError(pub String);
     /// Expects a BlobError from deserializer,
     /// Serialize as a RESP BlobError
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct BlobError(pub String);
     /// Expects a SimpleString from deserializer,
     /// Serialize as a RESP SimpleString
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct SimpleString(pub String);
     /// Expects a BlobString from deserializer,
     /// Serialize as a RESP BlobString
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct BlobString(pub String);
 
     macro_rules! impl_initializers {
@@ -53,6 +63,29 @@ pub mod owned {
                     $type_name(s.to_owned())
                 }
             }
+            impl $type_name {
+                /// Returns the inner string
+                pub fn as_str(&self) -> &str {
+                    &self.0
+                }
+
+                /// Unwraps the inner string
+                pub fn into_string(self) -> String {
+                    self.0
+                }
+            }
+            impl std::ops::Deref for $type_name {
+                type Target = str;
+
+                fn deref(&self) -> &str {
+                    &self.0
+                }
+            }
+            impl AsRef<str> for $type_name {
+                fn as_ref(&self) -> &str {
+                    &self.0
+                }
+            }
         };
     }
     impl_initializers!(SimpleError);
@@ -120,19 +153,19 @@ pub mod borrowed {
 
     /// Expects a SimpleError from deserializer,
     /// Serialize as a RESP SimpleError
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct SimpleError<'a>(pub Cow<'a, str>);
     /// Expects a BlobError from deserializer,
     /// Serialize as a RESP BlobError
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct BlobError<'a>(pub Cow<'a, str>);
     /// Expects a SimpleString from deserializer,
     /// Serialize as a RESP SimpleString
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct SimpleString<'a>(pub Cow<'a, str>);
     /// Expects a BlobString from deserializer,
     /// Serialize as a RESP BlobString
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     pub struct BlobString<'a>(pub Cow<'a, str>);
 
     macro_rules! impl_initializers {
@@ -147,6 +180,39 @@ pub mod borrowed {
                     $type_name(Cow::from(s))
                 }
             }
+            impl<$lt> From<$type_name<$lt>> for owned::$type_name {
+                fn from(s: $type_name<$lt>) -> Self {
+                    owned::$type_name(s.0.into_owned())
+                }
+            }
+            impl<$lt> From<&$lt owned::$type_name> for $type_name<$lt> {
+                fn from(s: &$lt owned::$type_name) -> Self {
+                    $type_name(Cow::from(s.as_str()))
+                }
+            }
+            impl<$lt> $type_name<$lt> {
+                /// Returns the inner string
+                pub fn as_str(&self) -> &str {
+                    &self.0
+                }
+
+                /// Unwraps the inner string, copying it if borrowed
+                pub fn into_string(self) -> String {
+                    self.0.into_owned()
+                }
+            }
+            impl<$lt> std::ops::Deref for $type_name<$lt> {
+                type Target = str;
+
+                fn deref(&self) -> &str {
+                    &self.0
+                }
+            }
+            impl<$lt> AsRef<str> for $type_name<$lt> {
+                fn as_ref(&self) -> &str {
+                    &self.0
+                }
+            }
         }
     }
     impl_initializers!(SimpleError<'a>);
@@ -177,6 +243,24 @@ pub mod borrowed {
                 {
                     Ok($type_name(Cow::from(v.to_owned())))
                 }
+
+                fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    std::str::from_utf8(v)
+                        .map(|s| $type_name(Cow::from(s)))
+                        .map_err(|_e| de::Error::invalid_value(de::Unexpected::Bytes(v), &self))
+                }
+
+                fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    std::str::from_utf8(v)
+                        .map(|s| $type_name(Cow::from(s.to_owned())))
+                        .map_err(|_e| de::Error::invalid_value(de::Unexpected::Bytes(v), &self))
+                }
             }
             impl<'de> Deserialize<'de> for $type_name<'de> {
                 fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -222,12 +306,6 @@ macro_rules! empty_visit {
     };
 }
 
-/// Custom struct to expect an attribute from [`crate::Deserializer`]
-/// and ignore its value
-pub(crate) struct AttributeSkip;
-/// Custom struct to expect a push value from [`crate::Deserializer`]
-/// and ignore its value
-pub(crate) struct PushSkip;
 /// Custom struct to expect a value from [`crate::Deserializer`],
 /// and ignore its value
 pub struct AnySkip;
@@ -279,7 +357,8 @@ impl<'de> Visitor<'de> for AnySkipVisitor {
     where
         D: serde::Deserializer<'de>,
     {
-        Deserialize::deserialize(deserializer)
+        AnySkip::deserialize(deserializer)?;
+        Ok(())
     }
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -307,26 +386,6 @@ impl<'de> Visitor<'de> for AnySkipVisitor {
     }
 }
 
-impl<'de> Deserialize<'de> for AttributeSkip {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_newtype_struct(ATTRIBUTE_SKIP_TOKEN, AnySkipVisitor)?;
-        Ok(AttributeSkip)
-    }
-}
-
-impl<'de> Deserialize<'de> for PushSkip {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_newtype_struct(PUSH_TOKEN, AnySkipVisitor)?;
-        Ok(PushSkip)
-    }
-}
-
 impl<'de> Deserialize<'de> for AnySkip {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -338,6 +397,11 @@ impl<'de> Deserialize<'de> for AnySkip {
 }
 
 /// Embed a RESP value V with an attribute A
+///
+/// Serializes as RESP3 specifies: the attribute map `|<len>` directly
+/// followed by the value it annotates, e.g. `|1\r\n+ttl\r\n:3600\r\n+v\r\n`.
+/// The value is not wrapped in an aggregate and the attribute doesn't count
+/// as an element of an enclosing aggregate.
 pub struct WithAttribute<A, V> {
     attr: A,
     value: V,
@@ -449,7 +513,191 @@ where
     }
 }
 
+/// Embed a RESP value V with its attribute kept as the encoded frame
+///
+/// Unlike [`WithAttribute`], the attribute is not parsed: `attribute()`
+/// returns its bytes, e.g. `|1\r\n+ttl\r\n:3600\r\n`, to be
+/// interpreted later with [`parse_attribute`](Self::parse_attribute), or
+/// never. The bytes are kept as received, from slices and io readers alike.
+/// Serializes the attribute bytes as is, followed by the value.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WithRawAttribute<V> {
+    attr: Vec<u8>,
+    value: V,
+}
+struct WithRawAttributeVisitor<V>(PhantomData<V>);
+
+/// Attribute frame bytes handed over by the deserializer
+struct RawAttributeBytes(Vec<u8>);
+
+impl<V> WithRawAttribute<V> {
+    /// Attach an already encoded attribute frame to a value
+    pub fn new(attr: Vec<u8>, value: V) -> Self {
+        WithRawAttribute { attr, value }
+    }
+
+    /// Encoded attribute frame
+    pub fn attribute(&self) -> &[u8] {
+        &self.attr
+    }
+
+    /// Parses the attribute frame into `A`, the way [`WithAttribute`] does
+    pub fn parse_attribute<'a, A: Deserialize<'a>>(&'a self) -> crate::Result<A> {
+        let mut deserializer = crate::Deserializer::from_slice(&self.attr);
+        deserializer.set_skip_attribute(false);
+        A::deserialize(&mut deserializer)
+    }
+
+    /// Unwrap underlying attribute frame and value
+    pub fn into_inner(self) -> (Vec<u8>, V) {
+        (self.attr, self.value)
+    }
+
+    /// Unwrap underlying value, drop the attribute
+    pub fn into_value(self) -> V {
+        self.value
+    }
+}
+
+impl<'de> Deserialize<'de> for RawAttributeBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RawAttributeBytesVisitor;
+
+        impl<'de> Visitor<'de> for RawAttributeBytesVisitor {
+            type Value = RawAttributeBytes;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "expecting attribute bytes")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawAttributeBytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawAttributeBytes(v))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(RawAttributeBytesVisitor)
+    }
+}
+
+impl<'de, V> Visitor<'de> for WithRawAttributeVisitor<V>
+where
+    V: Deserialize<'de>,
+{
+    type Value = WithRawAttribute<V>;
+
+    fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+    where
+        S: serde::de::SeqAccess<'de>,
+    {
+        let RawAttributeBytes(attr) = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &"2 expected"))?;
+        let value = seq
+            .next_element::<V>()?
+            .ok_or_else(|| de::Error::invalid_length(1, &"2 expected"))?;
+
+        Ok(WithRawAttribute { attr, value })
+    }
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expect seq (attribute bytes, value)")
+    }
+}
+
+impl<'de, V> Deserialize<'de> for WithRawAttribute<V>
+where
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple_struct(
+            WITH_RAW_ATTRIBUTE_TOKEN,
+            2,
+            WithRawAttributeVisitor::<V>(PhantomData),
+        )
+    }
+}
+
+impl<V> Serialize for WithRawAttribute<V>
+where
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(
+            WITH_ATTRIBUTE_TOKEN,
+            &WithAttributeInner {
+                attr: &RawRef(&self.attr),
+                value: &self.value,
+            },
+        )
+    }
+}
+
+/// Embed a RESP value V with an attribute computed by F from the value
+///
+/// Serialize only, the attribute is produced when serializing instead of
+/// being constructed upfront, then written the same way as [`WithAttribute`].
+pub struct AttributedWith<F, V> {
+    attr_fn: F,
+    value: V,
+}
+
+impl<F, V> AttributedWith<F, V> {
+    /// Attach an attribute computed by `attr_fn` to a value
+    pub fn new(attr_fn: F, value: V) -> Self {
+        AttributedWith { attr_fn, value }
+    }
+
+    /// Unwrap underlying value, drop the attribute function
+    pub fn into_value(self) -> V {
+        self.value
+    }
+}
+
+impl<F, A, V> Serialize for AttributedWith<F, V>
+where
+    F: Fn(&V) -> A,
+    A: Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let attr = (self.attr_fn)(&self.value);
+        serializer.serialize_newtype_struct(
+            WITH_ATTRIBUTE_TOKEN,
+            &WithAttributeInner {
+                attr: &attr,
+                value: &self.value,
+            },
+        )
+    }
+}
+
 /// Wraps a push value
+///
+/// An enum P is selected by the first element of the push, e.g.
+/// `>4\r\n+pmessage\r\n+pat\r\n+ch\r\n+hi\r\n` fills the `pmessage`
+/// variant with the remaining elements in order.
 pub struct Push<P>(pub P);
 
 impl<P> Push<P> {
@@ -458,9 +706,9 @@ impl<P> Push<P> {
     }
 }
 
-struct PushVisitor<'de, P>(&'de PhantomData<P>);
+struct PushVisitor<P>(PhantomData<P>);
 
-impl<'de, P> Visitor<'de> for PushVisitor<'de, P>
+impl<'de, P> Visitor<'de> for PushVisitor<P>
 where
     P: Deserialize<'de>,
 {
@@ -488,7 +736,7 @@ where
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_newtype_struct(PUSH_TOKEN, PushVisitor(&PhantomData))
+        deserializer.deserialize_newtype_struct(PUSH_TOKEN, PushVisitor(PhantomData))
     }
 }
 
@@ -504,6 +752,63 @@ where
     }
 }
 
+/// Wraps a set value, serialized as a RESP set (`~`) instead of an array,
+/// streamed (`~?`) when the length is unknown
+///
+/// Deserializing expects a set, use `T` directly to also accept arrays.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Set<T>(pub T);
+
+impl<T> Set<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+struct SetVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for SetVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Set<T>;
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Set)
+    }
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting newtype")
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Set<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(SET_TOKEN, SetVisitor(PhantomData))
+    }
+}
+
+impl<T> Serialize for Set<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(SET_TOKEN, &self.0)
+    }
+}
+
 /// Wraps a push value or a normal value.
 /// Returns Push variant if the next value from the input is a Redis' Push
 /// Returns Value variant otherwise
@@ -530,11 +835,11 @@ impl<P, V> PushOrValue<P, V> {
     }
 }
 
-struct PushOrValueVisitor<'de, P, V>(&'de PhantomData<(P, V)>);
+struct PushOrValueVisitor<P, V>(PhantomData<(P, V)>);
 
-impl<'de, P, V> Visitor<'de> for PushOrValueVisitor<'de, P, V>
+impl<'de, P, V> Visitor<'de> for PushOrValueVisitor<P, V>
 where
-    P: Deserialize<'de>,
+    P: Deserialize<'de> + 'de,
     V: Deserialize<'de>,
 {
     type Value = PushOrValue<P, V>;
@@ -558,7 +863,7 @@ where
                 Ok(PushOrValue::Value(val))
             }
             Some(v) => Err(de::Error::unknown_variant(v, &[PUSH_TOKEN, VALUE_TOKEN])),
-            None => Err(de::Error::invalid_length(0, &self))
+            None => Err(de::Error::invalid_length(0, &self)),
         }
     }
 }
@@ -572,7 +877,273 @@ where
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_newtype_struct(PUSH_OR_VALUE_TOKEN, PushOrValueVisitor(&PhantomData))
+        deserializer
+            .deserialize_newtype_struct(PUSH_OR_VALUE_TOKEN, PushOrValueVisitor(PhantomData))
+    }
+}
+
+/// An error reply (`-` or `!`), with the leading error code split from
+/// the message, e.g. `-WRONGTYPE Operation against a key` has code
+/// `WRONGTYPE` and message `Operation against a key`.
+///
+/// Only an uppercase first word is taken as the code, an error without
+/// one such as `-oops` has an empty code and the whole error as message.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct RedisError {
+    /// error code, the uppercase first word of the error, or empty
+    pub code: String,
+    /// rest of the error after the code
+    pub message: String,
+}
+
+impl RedisError {
+    fn parse(s: &str) -> Self {
+        let (code, message) = s.split_once(' ').unwrap_or((s, ""));
+        let is_code = !code.is_empty()
+            && code
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_' || b == b'-');
+        if is_code {
+            RedisError {
+                code: code.to_owned(),
+                message: message.to_owned(),
+            }
+        } else {
+            RedisError {
+                code: String::new(),
+                message: s.to_owned(),
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RedisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.code.is_empty() {
+            write!(f, "{}", self.message)
+        } else if self.message.is_empty() {
+            write!(f, "{}", self.code)
+        } else {
+            write!(f, "{} {}", self.code, self.message)
+        }
+    }
+}
+
+/// Serialize as a RESP SimpleError, or a BlobError if the recombined
+/// error contains CR/LF
+impl Serialize for RedisError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let s = self.to_string();
+        if s.contains(['\r', '\n']) {
+            serializer.serialize_newtype_struct(BLOB_ERROR_TOKEN, &s)
+        } else {
+            serializer.serialize_newtype_struct(SIMPLE_ERROR_TOKEN, &s)
+        }
+    }
+}
+
+impl std::error::Error for RedisError {}
+
+struct RedisErrorVisitor;
+
+impl<'de> Visitor<'de> for RedisErrorVisitor {
+    type Value = RedisError;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting simple or blob error")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RedisError::parse(v))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+
+    /// Tagged value from [`crate::Deserializer`], variant is the RESP type
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        let (kind, variant) = data.variant::<Kind>()?;
+        match kind {
+            Kind::SimpleError | Kind::BlobError => {
+                let s: String = de::VariantAccess::newtype_variant(variant)?;
+                Ok(RedisError::parse(&s))
+            }
+            _ => Err(de::Error::invalid_type(
+                de::Unexpected::Other("non-error reply"),
+                &self,
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RedisError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RESP_VALUE_TOKEN, RedisErrorVisitor)
+    }
+}
+
+/// A verbatim string (`=`), text with its 3 characters format, e.g.
+/// `=11\r\nmkd:# Title\r\n` has format `mkd` (markdown) and text
+/// `# Title`. Redis replies to `LOLWUT` and `CLIENT INFO` with them.
+///
+/// Serializing fails unless the format is exactly 3 ASCII characters.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct VerbatimString {
+    /// format of the text, `txt` or `mkd`
+    pub format: String,
+    /// the text, without the format prefix
+    pub text: String,
+}
+
+impl VerbatimString {
+    /// Creates a verbatim string from its format and text
+    pub fn new(format: impl Into<String>, text: impl Into<String>) -> Self {
+        VerbatimString {
+            format: format.into(),
+            text: text.into(),
+        }
+    }
+}
+
+impl Serialize for VerbatimString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let s = format!("{}:{}", self.format, self.text);
+        serializer.serialize_newtype_struct(VERBATIM_STRING_TOKEN, &s)
+    }
+}
+
+struct VerbatimStringVisitor;
+
+impl<'de> Visitor<'de> for VerbatimStringVisitor {
+    type Value = VerbatimString;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting verbatim string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let (format, text) = split_verbatim(v)?;
+        Ok(VerbatimString::new(format, text))
+    }
+
+    /// Tagged value from [`crate::Deserializer`], variant is the RESP type
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        let (kind, variant) = data.variant::<Kind>()?;
+        match kind {
+            Kind::VerbatimString => {
+                let s: String = de::VariantAccess::newtype_variant(variant)?;
+                self.visit_str(&s)
+            }
+            _ => Err(de::Error::invalid_type(
+                de::Unexpected::Other("non-verbatim reply"),
+                &self,
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VerbatimString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RESP_VALUE_TOKEN, VerbatimStringVisitor)
+    }
+}
+
+/// Reply to a command, either the expected value T or the error replied
+/// by the server instead.
+///
+/// ```rust
+/// use deseresp::types::CommandReply;
+///
+/// let reply: CommandReply<u64> = deseresp::from_slice(b":1\r\n").unwrap();
+/// assert_eq!(reply.into_result().unwrap(), 1);
+///
+/// let reply: CommandReply<u64> = deseresp::from_slice(b"-WRONGTYPE bad\r\n").unwrap();
+/// assert_eq!(reply.into_result().unwrap_err().code, "WRONGTYPE");
+/// ```
+#[derive(PartialEq, Debug)]
+pub struct CommandReply<T>(pub Result<T, RedisError>);
+
+impl<T> CommandReply<T> {
+    /// Unwrap into the value or the error reply
+    pub fn into_result(self) -> Result<T, RedisError> {
+        self.0
+    }
+}
+
+struct CommandReplyVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for CommandReplyVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = CommandReply<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting RESP value")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(|v| CommandReply(Ok(v)))
+    }
+
+    /// Tagged value from [`crate::Deserializer`], variant is the RESP type
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        let (kind, variant) = data.variant::<Kind>()?;
+        let reply = match kind {
+            Kind::SimpleError | Kind::BlobError => {
+                let s: String = de::VariantAccess::newtype_variant(variant)?;
+                Err(RedisError::parse(&s))
+            }
+            _ => Ok(de::VariantAccess::newtype_variant(variant)?),
+        };
+
+        Ok(CommandReply(reply))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for CommandReply<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RESP_VALUE_TOKEN, CommandReplyVisitor(PhantomData))
     }
 }
 
@@ -601,9 +1172,84 @@ impl Serialize for OkResponse {
     }
 }
 
+/// Pre-encoded RESP, written verbatim by [`Serializer`](crate::Serializer)
+/// without any marker or trailing `\r\n`, e.g. to splice a cached
+/// sub-reply into a larger value.
+///
+/// The bytes are not checked, they must be exactly one valid RESP frame
+/// or the output is corrupted.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Raw(pub Vec<u8>);
+
+/// Borrowed version of [`Raw`]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct RawRef<'a>(pub &'a [u8]);
+
+impl Serialize for Raw {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_TOKEN, &Bytes(&self.0))
+    }
+}
+
+impl Serialize for RawRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_TOKEN, &Bytes(self.0))
+    }
+}
+
+/// Captures the bytes of the next complete RESP value, nested aggregates
+/// included, without interpreting them, e.g. for proxies forwarding a
+/// sub-reply untouched. Serializes back verbatim like [`RawRef`].
+///
+/// Only deserializers borrowing their input, such as
+/// [`Deserializer::from_slice`](crate::Deserializer::from_slice), support it.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct RawFrame<'de>(pub &'de [u8]);
+
+struct RawFrameVisitor;
+
+impl<'de> Visitor<'de> for RawFrameVisitor {
+    type Value = RawFrame<'de>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting borrowed bytes")
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawFrame(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawFrame<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RAW_TOKEN, RawFrameVisitor)
+    }
+}
+
+impl Serialize for RawFrame<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        RawRef(self.0).serialize(serializer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::borrow::Cow;
+    use std::{borrow::Cow, collections::BTreeMap};
 
     use super::*;
     use crate::{test_utils::test_deserialize, to_vec};
@@ -627,6 +1273,23 @@ mod tests {
         assert_eq!(buf, b"!5\r\nhello\r\n");
     }
 
+    #[test]
+    fn serialize_borrowed_types_owned_cow() {
+        for s in ["hello", "héllo ✓"] {
+            let by_ref = to_vec(&borrowed::BlobString(Cow::Borrowed(s))).unwrap();
+            let by_val = to_vec(&borrowed::BlobString(Cow::Owned(s.to_owned()))).unwrap();
+            assert_eq!(by_ref, by_val);
+            assert_eq!(by_val, format!("${}\r\n{}\r\n", s.len(), s).as_bytes());
+
+            let value: borrowed::BlobString = crate::from_slice(&by_val).unwrap();
+            assert_eq!(value.as_str(), s);
+        }
+
+        // length is in bytes, "héllo ✓" is 7 chars
+        let buf = to_vec(&borrowed::BlobString(Cow::Owned("héllo ✓".into()))).unwrap();
+        assert!(buf.starts_with(b"$10\r\n"));
+    }
+
     #[test]
     fn serialize_owned_types() {
         let ss = owned::SimpleString::from("hello");
@@ -646,6 +1309,53 @@ mod tests {
         assert_eq!(buf, b"!5\r\nhello\r\n");
     }
 
+    #[test]
+    fn convert_owned_borrowed_types() {
+        let owned = owned::BlobString::from("hello");
+        let borrowed = borrowed::BlobString::from(&owned);
+        assert!(matches!(borrowed.0, Cow::Borrowed("hello")));
+        assert_eq!(borrowed.as_str(), "hello");
+        assert_eq!(owned::BlobString::from(borrowed), owned);
+        assert_eq!(owned.into_string(), "hello");
+
+        let borrowed = borrowed::SimpleError::from("ERR oops");
+        let owned = owned::SimpleError::from(borrowed);
+        assert_eq!(owned.as_str(), "ERR oops");
+        assert_eq!(
+            borrowed::SimpleError::from(&owned).into_string(),
+            "ERR oops"
+        );
+
+        let owned = owned::SimpleString::from("OK");
+        assert_eq!(
+            borrowed::SimpleString::from(&owned).as_str(),
+            owned.as_str()
+        );
+        let borrowed = borrowed::BlobError::from(String::from("ERR"));
+        assert_eq!(owned::BlobError::from(borrowed).into_string(), "ERR");
+    }
+
+    #[test]
+    fn deref_string_types() {
+        fn takes_str(s: impl AsRef<str>) -> usize {
+            s.as_ref().len()
+        }
+
+        let owned = owned::SimpleString::from("hello world");
+        assert!(owned.starts_with("hello"));
+        assert_eq!(takes_str(&*owned), 11);
+        let err = owned::BlobError::from("ERR oops");
+        assert_eq!(err.split_once(' '), Some(("ERR", "oops")));
+        assert_eq!(takes_str(err), 8);
+
+        let borrowed = borrowed::BlobString::from("café");
+        assert_eq!(borrowed.len(), 5);
+        assert_eq!(takes_str(&*borrowed), 5);
+        let err = borrowed::SimpleError::from(String::from("WRONGTYPE"));
+        assert_eq!(err.to_lowercase(), "wrongtype");
+        assert_eq!(takes_str(err), 9);
+    }
+
     #[test]
     fn deserialize_borrowed_types() {
         test_deserialize(b"+hello world\r\n", |value: borrowed::SimpleString| {
@@ -665,6 +1375,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deserialize_borrowed_types_from_bytes() {
+        use serde::de::value::{BorrowedBytesDeserializer, BytesDeserializer, Error};
+
+        let d = BorrowedBytesDeserializer::<Error>::new(b"hello");
+        let value = borrowed::SimpleString::deserialize(d).unwrap();
+        assert!(matches!(value.0, Cow::Borrowed("hello")));
+
+        let d = BytesDeserializer::<Error>::new(b"hello");
+        let value = borrowed::BlobString::deserialize(d).unwrap();
+        assert!(matches!(value.0, Cow::Owned(s) if s == "hello"));
+
+        let d = BorrowedBytesDeserializer::<Error>::new(b"ERR\xff");
+        assert!(borrowed::SimpleError::deserialize(d).is_err());
+        let d = BytesDeserializer::<Error>::new(b"ERR\xff");
+        assert!(borrowed::BlobError::deserialize(d).is_err());
+    }
+
     #[test]
     fn deserialize_owned_types() {
         test_deserialize(b"+hello world\r\n", |value: owned::SimpleString| {
@@ -753,7 +1481,18 @@ mod tests {
         });
 
         test_deserialize(b"|1\r\n+hello\r\n+world\r\n#t\r\n", |value: bool| {
-            assert_eq!(value, true);
+            assert!(value);
+        });
+    }
+
+    #[test]
+    fn test_ignore_push() {
+        let input = b">2\r\n+invalidate\r\n*1\r\n+k\r\n$5\r\nalice\r\n";
+        test_deserialize(input, |value: String| {
+            assert_eq!(value, "alice");
+        });
+        test_deserialize(b"*2\r\n:1\r\n>1\r\n_\r\n:2\r\n", |value: (u64, u64)| {
+            assert_eq!(value, (1, 2));
         });
     }
 
@@ -789,6 +1528,85 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_attribute_on_map_value() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Reply {
+            k: u64,
+        }
+        let input = b"%1\r\n+k\r\n|1\r\n+attr\r\n+x\r\n:5\r\n";
+        test_deserialize(input, |value: BTreeMap<String, u64>| {
+            assert_eq!(value, BTreeMap::from([("k".to_owned(), 5)]));
+        });
+        test_deserialize(input, |value: Reply| {
+            assert_eq!(value, Reply { k: 5 });
+        });
+        let mut d = crate::Deserializer::from_slice(input).assume_ordered_map(true);
+        assert_eq!(Reply::deserialize(&mut d).unwrap(), Reply { k: 5 });
+
+        // round trip, the attribute is kept or skipped by the target type
+        let value = BTreeMap::from([("k", WithAttribute::new(BTreeMap::from([("attr", "x")]), 5))]);
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(s(&buf), s(input));
+        test_deserialize(&buf, |value: Reply| {
+            assert_eq!(value, Reply { k: 5 });
+        });
+        test_deserialize(
+            &buf,
+            |value: BTreeMap<String, WithAttribute<BTreeMap<String, String>, u64>>| {
+                let (attr, value) = value.into_values().next().unwrap().into_inner();
+                assert_eq!(attr, BTreeMap::from([("attr".to_owned(), "x".to_owned())]));
+                assert_eq!(value, 5);
+            },
+        );
+    }
+
+    #[test]
+    fn test_serialize_attribute_spec_example() {
+        // the key-popularity reply from the RESP3 spec, with blob string keys
+        struct KeyPop;
+        impl Serialize for KeyPop {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+
+                let mut map = s.serialize_map(Some(2))?;
+                map.serialize_entry(&owned::BlobString::from("a"), &0.1923)?;
+                map.serialize_entry(&owned::BlobString::from("b"), &0.0012)?;
+                map.end()
+            }
+        }
+        #[derive(Serialize)]
+        struct Meta {
+            #[serde(rename = "key-popularity")]
+            key_popularity: KeyPop,
+        }
+        let value = WithAttribute::new(
+            Meta {
+                key_popularity: KeyPop,
+            },
+            (2039123, 9543892),
+        );
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(
+            s(&buf),
+            s(b"|1\r\n+key-popularity\r\n%2\r\n$1\r\na\r\n,0.1923\r\n$1\r\nb\r\n,0.0012\r\n*2\r\n:2039123\r\n:9543892\r\n")
+        );
+
+        // attribute on an array element, as in the spec, isn't counted as an element
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Ttl {
+            ttl: u64,
+        }
+        let value = (WithAttribute::new(Ttl { ttl: 3600 }, "a"), "b");
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(s(&buf), s(b"*2\r\n|1\r\n+ttl\r\n:3600\r\n+a\r\n+b\r\n"));
+        test_deserialize(&buf, |value: (WithAttribute<Ttl, String>, String)| {
+            let (with_attr, b) = value;
+            assert_eq!(with_attr.into_inner(), (Ttl { ttl: 3600 }, "a".into()));
+            assert_eq!(b, "b");
+        });
+    }
+
     #[test]
     fn test_nested_deserialize_attribute() {
         //  |1\r\n
@@ -836,6 +1654,42 @@ mod tests {
                 assert_eq!(value, 300);
             },
         );
+
+        //  |1\r\n
+        //      +a\r\n
+        //      :1\r\n
+        //  |1\r\n
+        //      +b\r\n
+        //      :2\r\n
+        //  :300\r\n
+        let input = b"|1\r\n+a\r\n:1\r\n|1\r\n+b\r\n:2\r\n:300\r\n";
+        test_deserialize(
+            input,
+            |with_attr: WithAttribute<BTreeMap<String, u64>, WithAttribute<Test2, usize>>| {
+                let (outer, inner) = with_attr.into_inner();
+                let (inner, value) = inner.into_inner();
+                assert_eq!(outer, BTreeMap::from([("a".to_owned(), 1)]));
+                assert_eq!(inner.b, 2);
+                assert_eq!(value, 300);
+            },
+        );
+        // the value's own attribute is skipped when not asked for
+        test_deserialize(
+            input,
+            |with_attr: WithAttribute<BTreeMap<String, u64>, usize>| {
+                assert_eq!(with_attr.into_value(), 300);
+            },
+        );
+        test_deserialize(
+            b"*2\r\n|1\r\n+a\r\n:1\r\n|1\r\n+b\r\n:2\r\n:300\r\n:400\r\n",
+            |value: (WithAttribute<BTreeMap<String, u64>, u64>, u64)| {
+                assert_eq!((value.0.into_value(), value.1), (300, 400));
+            },
+        );
+        #[derive(Deserialize)]
+        struct Test2 {
+            b: usize,
+        }
     }
 
     fn s(b: &[u8]) -> &str {
@@ -853,6 +1707,54 @@ mod tests {
         assert_eq!(s(&buf), s(b"|1\r\n+a\r\n:200\r\n:300\r\n"));
     }
 
+    #[test]
+    fn test_serialize_attributed_with() {
+        #[derive(Serialize)]
+        struct Attr {
+            len: usize,
+        }
+        let value = AttributedWith::new(|v: &Vec<u64>| Attr { len: v.len() }, vec![1, 2]);
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(s(&buf), s(b"|1\r\n+len\r\n:2\r\n*2\r\n:1\r\n:2\r\n"));
+
+        let value = vec![AttributedWith::new(|v: &&str| Attr { len: v.len() }, "abc")];
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(s(&buf), s(b"*1\r\n|1\r\n+len\r\n:3\r\n+abc\r\n"));
+    }
+
+    #[test]
+    fn test_with_raw_attribute() {
+        let attr: &[u8] = b"|1\r\n$3\r\nttl\r\n:3600\r\n";
+        let input = b"|1\r\n$3\r\nttl\r\n:3600\r\n*2\r\n:1\r\n:2\r\n";
+        test_deserialize(input, |value: WithRawAttribute<Vec<u64>>| {
+            assert_eq!(value.attribute(), attr);
+            assert_eq!(to_vec(&value).unwrap(), input);
+            assert_eq!(value.into_value(), [1, 2]);
+        });
+
+        let input = b"*2\r\n|1\r\n$3\r\nttl\r\n:3600\r\n+a\r\n+b\r\n";
+        test_deserialize(input, |value: (WithRawAttribute<String>, String)| {
+            let (raw, a) = value.0.into_inner();
+            assert_eq!((&raw[..], &a[..], &value.1[..]), (attr, "a", "b"));
+        });
+
+        // the attribute is parsed on demand, only when needed
+        let mut d = crate::Deserializer::from_slice(input);
+        let (value, _): (WithRawAttribute<&str>, &str) = Deserialize::deserialize(&mut d).unwrap();
+        let ttl: BTreeMap<&str, u64> = value.parse_attribute().unwrap();
+        assert_eq!(ttl, BTreeMap::from([("ttl", 3600)]));
+
+        // the bytes are kept as received
+        let input = b"|1\r\n+score\r\n,1.50\r\n:7\r\n";
+        let value: WithRawAttribute<u64> = crate::from_slice(input).unwrap();
+        assert_eq!(value.attribute(), b"|1\r\n+score\r\n,1.50\r\n");
+        let value: WithRawAttribute<u64> = crate::from_read(&input[..]).unwrap();
+        assert_eq!(value.attribute(), b"|1\r\n+score\r\n,1.50\r\n");
+
+        let value: Result<WithRawAttribute<u64>, _> = crate::from_slice(b":7\r\n");
+        assert!(value.is_err());
+    }
+
     #[test]
     fn test_serialize_nested_attribute() {
         #[derive(Serialize)]
@@ -876,6 +1778,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_redis_error() {
+        test_deserialize(
+            b"-WRONGTYPE Operation against a key\r\n",
+            |value: RedisError| {
+                assert_eq!(value.code, "WRONGTYPE");
+                assert_eq!(value.message, "Operation against a key");
+                assert_eq!(
+                    to_vec(&value).unwrap(),
+                    b"-WRONGTYPE Operation against a key\r\n"
+                );
+            },
+        );
+        test_deserialize(b"!10\r\nERR a\r\nb c\r\n", |value: RedisError| {
+            assert_eq!(value.code, "ERR");
+            assert_eq!(value.message, "a\r\nb c");
+            assert_eq!(to_vec(&value).unwrap(), b"!10\r\nERR a\r\nb c\r\n");
+        });
+
+        // a bare code has an empty message
+        test_deserialize(b"-ERR\r\n", |value: RedisError| {
+            assert_eq!(value.code, "ERR");
+            assert_eq!(value.message, "");
+            assert_eq!(value.to_string(), "ERR");
+            assert_eq!(to_vec(&value).unwrap(), b"-ERR\r\n");
+        });
+        // no code, the whole error is the message
+        test_deserialize(b"-oops\r\n", |value: RedisError| {
+            assert_eq!(value.code, "");
+            assert_eq!(value.message, "oops");
+            assert_eq!(value.to_string(), "oops");
+            assert_eq!(to_vec(&value).unwrap(), b"-oops\r\n");
+        });
+        test_deserialize(b"-not found here\r\n", |value: RedisError| {
+            assert_eq!(value.code, "");
+            assert_eq!(value.message, "not found here");
+        });
+
+        let value: crate::Result<RedisError> = crate::from_slice(b"+OK\r\n");
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_verbatim_string() {
+        let value = VerbatimString::new("mkd", "# Title");
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(buf, b"=11\r\nmkd:# Title\r\n");
+        test_deserialize(&buf, |decoded: VerbatimString| {
+            assert_eq!(decoded, value);
+        });
+        test_deserialize(b"=10\r\ntxt:a\r\nb:c\r\n", |value: VerbatimString| {
+            assert_eq!(value, VerbatimString::new("txt", "a\r\nb:c"));
+        });
+
+        for format in ["md", "mark", "", "a:b", "m d", "é"] {
+            let err = to_vec(&VerbatimString::new(format, "x")).unwrap_err();
+            assert_eq!(
+                err,
+                crate::Error::unexpected_value("verbatim string format")
+            );
+        }
+        let value: crate::Result<VerbatimString> = crate::from_slice(b"+txt:a\r\n");
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_command_reply() {
+        test_deserialize(b":1\r\n", |value: CommandReply<u64>| {
+            assert_eq!(value, CommandReply(Ok(1)));
+        });
+        test_deserialize(
+            b"*2\r\n+a\r\n_\r\n",
+            |value: CommandReply<Vec<Option<String>>>| {
+                assert_eq!(value.into_result().unwrap(), [Some("a".into()), None]);
+            },
+        );
+        test_deserialize(b"_\r\n", |value: CommandReply<Option<u64>>| {
+            assert_eq!(value, CommandReply(Ok(None)));
+        });
+        test_deserialize(
+            b"-WRONGTYPE Operation against a key\r\n",
+            |value: CommandReply<u64>| {
+                let err = value.into_result().unwrap_err();
+                assert_eq!(err.code, "WRONGTYPE");
+                assert_eq!(err.message, "Operation against a key");
+            },
+        );
+        test_deserialize(
+            b"!21\r\nSYNTAX invalid syntax\r\n",
+            |value: CommandReply<u64>| {
+                let err = value.into_result().unwrap_err();
+                assert_eq!(err.code, "SYNTAX");
+                assert_eq!(err.message, "invalid syntax");
+            },
+        );
+        test_deserialize(
+            b"|1\r\n+a\r\n:1\r\n-ERR x\r\n",
+            |value: CommandReply<u64>| {
+                assert_eq!(value.into_result().unwrap_err().code, "ERR");
+            },
+        );
+
+        // a value not matching T is still a deserialize error
+        let value: crate::Result<CommandReply<u64>> = crate::from_slice(b"+OK\r\n");
+        assert!(value.is_err());
+    }
+
     #[test]
     fn test_deserialize_push_or_value_type() {
         #[derive(Deserialize)]
@@ -904,4 +1913,49 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_deserialize_set() {
+        test_deserialize(b"~2\r\n:1\r\n:2\r\n", |value: Set<Vec<u64>>| {
+            assert_eq!(value.into_inner(), [1, 2]);
+        });
+        test_deserialize(b"|1\r\n+a\r\n:1\r\n~0\r\n", |value: Set<Vec<u64>>| {
+            assert!(value.0.is_empty());
+        });
+        let value: crate::Result<Set<Vec<u64>>> = crate::from_slice(b"*1\r\n:1\r\n");
+        let err = crate::Error::expected_marker_found("'~' for Set", b'*');
+        assert_eq!(value.unwrap_err(), err);
+
+        let value = crate::Value::Set(vec![crate::Value::Integer(1)]);
+        assert_eq!(crate::from_value::<Set<Vec<u64>>>(value).unwrap().0, [1]);
+    }
+
+    #[test]
+    fn test_raw_frame() {
+        let input = b":1\r\n$3\r\nabc\r\n";
+        let mut d = crate::Deserializer::from_slice(input);
+        let first = RawFrame::deserialize(&mut d).unwrap();
+        let second = RawFrame::deserialize(&mut d).unwrap();
+        assert_eq!(first.0, b":1\r\n");
+        assert_eq!(second.0, b"$3\r\nabc\r\n");
+        d.expect_eof().unwrap();
+
+        // nested aggregates are captured whole, borrowed from the input
+        let input = b"*2\r\n*3\r\n%1\r\n+a\r\n*2\r\n:1\r\n~1\r\n_\r\n$1\r\nb\r\n,1.5\r\n:7\r\n";
+        let value: (RawFrame, i64) = crate::from_slice(input).unwrap();
+        assert_eq!(value.0 .0, &input[4..input.len() - 4]);
+        assert_eq!(value.0 .0.as_ptr(), input[4..].as_ptr());
+        assert_eq!(value.1, 7);
+        assert_eq!(to_vec(&value).unwrap(), input);
+
+        // skipped attributes are not part of the frame
+        let value: RawFrame = crate::from_slice(b"|1\r\n+ttl\r\n:3\r\n+v\r\n").unwrap();
+        assert_eq!(value.0, b"+v\r\n");
+
+        let value: crate::Result<RawFrame> = crate::from_slice(b"*2\r\n:1\r\n");
+        assert!(value.unwrap_err().is_eof());
+
+        let mut d = crate::Deserializer::from_read(&b":1\r\n"[..]);
+        assert!(RawFrame::deserialize(&mut d).is_err());
+    }
 }
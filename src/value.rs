@@ -0,0 +1,1677 @@
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{
+        self,
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+        DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+    },
+    Deserialize, Serialize,
+};
+
+use crate::{
+    error::str_from_utf8,
+    markers,
+    types::{
+        BIG_NUMBER_TOKEN, BLOB_ERROR_TOKEN, BLOB_STRING_TOKEN, PUSH_TOKEN, RESP_FRAME_TOKEN,
+        RESP_VALUE_TOKEN, SET_TOKEN, SIMPLE_ERROR_TOKEN, SIMPLE_STRING_TOKEN,
+        VERBATIM_STRING_TOKEN,
+    },
+    Deserializer, Error,
+};
+
+/// A dynamically typed RESP value, preserving the RESP type of the input
+///
+/// The RESP type is only known when deserialized directly from a
+/// [`Deserializer`]; values buffered by serde first, e.g. under
+/// `#[serde(flatten)]`, are rebuilt from their data model type, so strings
+/// become [`Value::SimpleString`] and sets become [`Value::Array`].
+///
+/// Unlike other types, which skip the pushes before a reply, a push is
+/// read as [`Value::Push`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `+<string>`
+    SimpleString(String),
+    /// `-<string>`
+    SimpleError(String),
+    /// `$<len>`, binary safe
+    BlobString(Vec<u8>),
+    /// `!<len>`
+    BlobError(String),
+    /// `=<len>`, with the 3 characters format (`txt`, `mkd`) split from the text
+    VerbatimString {
+        /// format of the text
+        format: String,
+        /// text content
+        text: String,
+    },
+    /// `:<number>`
+    Integer(i64),
+    /// `,<double>`
+    Double(f64),
+    /// `#t` or `#f`
+    Boolean(bool),
+    /// `(<big number>`
+    BigNumber(String),
+    /// `_`
+    Null,
+    /// `*<len>`
+    Array(Vec<Value>),
+    /// `~<len>`, read as [`Value::Array`] when serde buffers the input
+    /// (untagged enums, `#[serde(flatten)]`), which only tells sequences
+    Set(Vec<Value>),
+    /// `%<len>`, keep pairs in the order received
+    Map(Vec<(Value, Value)>),
+    /// `><len>`
+    Push(Vec<Value>),
+}
+
+/// A RESP value borrowing its strings from the input, see [`Value`]
+///
+/// Only deserializable from a slice, as in [`crate::from_slice`], strings
+/// and blobs point into the input instead of being copied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'de> {
+    /// `+<string>`
+    SimpleString(&'de str),
+    /// `-<string>`
+    SimpleError(&'de str),
+    /// `$<len>`, binary safe
+    BlobString(&'de [u8]),
+    /// `!<len>`
+    BlobError(&'de str),
+    /// `=<len>`, with the 3 characters format (`txt`, `mkd`) split from the text
+    VerbatimString {
+        /// format of the text
+        format: &'de str,
+        /// text content
+        text: &'de str,
+    },
+    /// `:<number>`
+    Integer(i64),
+    /// `,<double>`
+    Double(f64),
+    /// `#t` or `#f`
+    Boolean(bool),
+    /// `(<big number>`
+    BigNumber(&'de str),
+    /// `_`
+    Null,
+    /// `*<len>`
+    Array(Vec<ValueRef<'de>>),
+    /// `~<len>`
+    Set(Vec<ValueRef<'de>>),
+    /// `%<len>`, keep pairs in the order received
+    Map(Vec<(ValueRef<'de>, ValueRef<'de>)>),
+    /// `><len>`
+    Push(Vec<ValueRef<'de>>),
+}
+
+/// A frame received from a RESP3 connection, server pushes can be
+/// interleaved with command replies at any time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// An out-of-band push, always a [`Value::Push`]
+    Push(Value),
+    /// A reply to a command
+    Reply(Value),
+}
+
+/// A scalar RESP value, with the variant chosen by the type marker.
+///
+/// Strings of any kind (simple, blob, verbatim without its format, and
+/// errors) become [`AnyScalar::Text`], big numbers become
+/// [`AnyScalar::Int`] or [`AnyScalar::UInt`] when they fit. Aggregates
+/// are rejected, use [`Value`] to accept them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyScalar {
+    /// `:<number>`, or a big number in range of i64
+    Int(i64),
+    /// a big number above i64 range
+    UInt(u64),
+    /// `,<double>`
+    Double(f64),
+    /// `#t` or `#f`
+    Bool(bool),
+    /// `+`, `-`, `$`, `!` or `=`
+    Text(String),
+    /// `_`
+    Null,
+}
+
+/// RESP type of the next value, see [`Deserializer::peek_type`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RespType {
+    /// `+`
+    SimpleString,
+    /// `$`
+    BlobString,
+    /// `=`
+    VerbatimString,
+    /// `-` or `!`
+    Error,
+    /// `:`
+    Integer,
+    /// `,`
+    Double,
+    /// `#`
+    Boolean,
+    /// `(`
+    BigNumber,
+    /// `_`
+    Null,
+    /// `*`
+    Array,
+    /// `~`
+    Set,
+    /// `%`
+    Map,
+    /// `>`
+    Push,
+    /// `|`, only seen when attributes are not skipped
+    Attribute,
+}
+
+impl RespType {
+    pub(crate) fn from_marker(marker: u8) -> Option<Self> {
+        let resp_type = match marker {
+            markers::SIMPLE_STRING => RespType::SimpleString,
+            markers::BLOB_STRING => RespType::BlobString,
+            markers::VERBATIM_STRING => RespType::VerbatimString,
+            markers::SIMPLE_ERROR | markers::BLOB_ERROR => RespType::Error,
+            markers::INTEGER => RespType::Integer,
+            markers::DOUBLE => RespType::Double,
+            markers::BOOLEAN => RespType::Boolean,
+            markers::BIG_NUMBER => RespType::BigNumber,
+            markers::NULL => RespType::Null,
+            markers::ARRAY => RespType::Array,
+            markers::SET => RespType::Set,
+            markers::MAP => RespType::Map,
+            markers::PUSH => RespType::Push,
+            markers::ATTRIBUTE => RespType::Attribute,
+            _ => return None,
+        };
+
+        Some(resp_type)
+    }
+}
+
+macro_rules! kinds {
+    ($($kind:ident),* $(,)?) => {
+        /// RESP type of a value, used by the Deserializer to tag the next value
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        pub(crate) enum Kind {
+            $($kind),*
+        }
+
+        /// Variant names of [`Kind`], also the variants of [`Value`]
+        const KINDS: &[&str] = &[$(stringify!($kind)),*];
+
+        impl Kind {
+            /// Variant name, read back by [`KindVisitor`]
+            pub(crate) fn name(self) -> &'static str {
+                KINDS[self as usize]
+            }
+
+            fn from_name(name: &str) -> Option<Kind> {
+                [$(Kind::$kind),*].into_iter().find(|kind| kind.name() == name)
+            }
+        }
+    };
+}
+
+kinds!(
+    SimpleString,
+    SimpleError,
+    BlobString,
+    BlobError,
+    VerbatimString,
+    Integer,
+    Double,
+    Boolean,
+    BigNumber,
+    Null,
+    Array,
+    Set,
+    Map,
+    Push,
+);
+
+impl Kind {
+    pub(crate) fn from_marker(marker: u8) -> Option<Kind> {
+        let kind = match marker {
+            markers::SIMPLE_STRING => Kind::SimpleString,
+            markers::SIMPLE_ERROR => Kind::SimpleError,
+            markers::BLOB_STRING => Kind::BlobString,
+            markers::BLOB_ERROR => Kind::BlobError,
+            markers::VERBATIM_STRING => Kind::VerbatimString,
+            markers::INTEGER => Kind::Integer,
+            markers::DOUBLE => Kind::Double,
+            markers::BOOLEAN => Kind::Boolean,
+            markers::BIG_NUMBER => Kind::BigNumber,
+            markers::NULL => Kind::Null,
+            markers::ARRAY => Kind::Array,
+            markers::SET => Kind::Set,
+            markers::MAP | markers::ATTRIBUTE => Kind::Map,
+            markers::PUSH => Kind::Push,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    /// Marker of the kind, the inverse of [`Kind::from_marker`]
+    pub(crate) fn marker(self) -> u8 {
+        match self {
+            Kind::SimpleString => markers::SIMPLE_STRING,
+            Kind::SimpleError => markers::SIMPLE_ERROR,
+            Kind::BlobString => markers::BLOB_STRING,
+            Kind::BlobError => markers::BLOB_ERROR,
+            Kind::VerbatimString => markers::VERBATIM_STRING,
+            Kind::Integer => markers::INTEGER,
+            Kind::Double => markers::DOUBLE,
+            Kind::Boolean => markers::BOOLEAN,
+            Kind::BigNumber => markers::BIG_NUMBER,
+            Kind::Null => markers::NULL,
+            Kind::Array => markers::ARRAY,
+            Kind::Set => markers::SET,
+            Kind::Map => markers::MAP,
+            Kind::Push => markers::PUSH,
+        }
+    }
+}
+
+struct KindVisitor;
+
+impl<'de> Visitor<'de> for KindVisitor {
+    type Value = Kind;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "expecting RESP type")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Kind::from_name(v).ok_or_else(|| de::Error::unknown_variant(v, KINDS))
+    }
+}
+
+impl<'de> Deserialize<'de> for Kind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(KindVisitor)
+    }
+}
+
+struct ByteBuf(Vec<u8>);
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "expecting bytes")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+struct MapEntries<T>(Vec<(T, T)>);
+struct MapEntriesVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for MapEntriesVisitor<T> {
+    type Value = MapEntries<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "expecting map")
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        collect_entries(map).map(MapEntries)
+    }
+}
+
+/// Size hint comes from the input's length prefix, don't trust it
+/// for preallocation
+fn cautious(hint: Option<usize>) -> usize {
+    hint.unwrap_or(0).min(4096)
+}
+
+fn collect_entries<'de, A, T>(mut map: A) -> Result<Vec<(T, T)>, A::Error>
+where
+    A: de::MapAccess<'de>,
+    T: Deserialize<'de>,
+{
+    let mut entries = Vec::with_capacity(cautious(map.size_hint()));
+    while let Some(entry) = map.next_entry()? {
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for MapEntries<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapEntriesVisitor(PhantomData))
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "expecting any RESP value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Boolean(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Value::Integer(v)),
+            Err(_) => Ok(Value::BigNumber(v.to_string())),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::SimpleString(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::SimpleString(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::BlobString(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::BlobString(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(cautious(seq.size_hint()));
+        while let Some(v) = seq.next_element()? {
+            values.push(v);
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        collect_entries(map).map(Value::Map)
+    }
+
+    /// Tagged value from [`crate::Deserializer`], variant is the RESP type
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (kind, variant) = data.variant()?;
+        let value = match kind {
+            Kind::SimpleString => Value::SimpleString(variant.newtype_variant()?),
+            Kind::SimpleError => Value::SimpleError(variant.newtype_variant()?),
+            Kind::BlobString => Value::BlobString(variant.newtype_variant::<ByteBuf>()?.0),
+            Kind::BlobError => Value::BlobError(variant.newtype_variant()?),
+            Kind::VerbatimString => {
+                let s: String = variant.newtype_variant()?;
+                let (format, text) = split_verbatim(&s)?;
+                Value::VerbatimString {
+                    format: format.to_owned(),
+                    text: text.to_owned(),
+                }
+            }
+            Kind::Integer => Value::Integer(variant.newtype_variant()?),
+            Kind::Double => Value::Double(variant.newtype_variant()?),
+            Kind::Boolean => Value::Boolean(variant.newtype_variant()?),
+            Kind::BigNumber => Value::BigNumber(variant.newtype_variant()?),
+            Kind::Null => {
+                variant.unit_variant()?;
+                Value::Null
+            }
+            Kind::Array => Value::Array(variant.newtype_variant()?),
+            Kind::Set => Value::Set(variant.newtype_variant()?),
+            Kind::Map => Value::Map(variant.newtype_variant::<MapEntries<_>>()?.0),
+            Kind::Push => Value::Push(variant.newtype_variant()?),
+        };
+
+        Ok(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RESP_FRAME_TOKEN, ValueVisitor)
+    }
+}
+
+pub(crate) fn split_verbatim<E: de::Error>(s: &str) -> Result<(&str, &str), E> {
+    match s.split_once(':') {
+        Some((format, text)) if format.len() == 3 => Ok((format, text)),
+        _ => Err(de::Error::invalid_value(
+            de::Unexpected::Str(s),
+            &"verbatim string with 3 characters format",
+        )),
+    }
+}
+
+struct ValueRefVisitor;
+
+impl<'de> Visitor<'de> for ValueRefVisitor {
+    type Value = ValueRef<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "expecting any RESP value borrowed from the input"
+        )
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Boolean(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Integer(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Double(v))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::SimpleString(v))
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::BlobString(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Null)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(cautious(seq.size_hint()));
+        while let Some(v) = seq.next_element()? {
+            values.push(v);
+        }
+
+        Ok(ValueRef::Array(values))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        collect_entries(map).map(ValueRef::Map)
+    }
+
+    /// Tagged value from [`crate::Deserializer`], variant is the RESP type
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (kind, variant) = data.variant()?;
+        let value = match kind {
+            Kind::SimpleString => ValueRef::SimpleString(variant.newtype_variant()?),
+            Kind::SimpleError => ValueRef::SimpleError(variant.newtype_variant()?),
+            Kind::BlobString => ValueRef::BlobString(variant.newtype_variant()?),
+            Kind::BlobError => ValueRef::BlobError(variant.newtype_variant()?),
+            Kind::VerbatimString => {
+                let (format, text) = split_verbatim(variant.newtype_variant()?)?;
+                ValueRef::VerbatimString { format, text }
+            }
+            Kind::Integer => ValueRef::Integer(variant.newtype_variant()?),
+            Kind::Double => ValueRef::Double(variant.newtype_variant()?),
+            Kind::Boolean => ValueRef::Boolean(variant.newtype_variant()?),
+            Kind::BigNumber => ValueRef::BigNumber(variant.newtype_variant()?),
+            Kind::Null => {
+                variant.unit_variant()?;
+                ValueRef::Null
+            }
+            Kind::Array => ValueRef::Array(variant.newtype_variant()?),
+            Kind::Set => ValueRef::Set(variant.newtype_variant()?),
+            Kind::Map => ValueRef::Map(variant.newtype_variant::<MapEntries<_>>()?.0),
+            Kind::Push => ValueRef::Push(variant.newtype_variant()?),
+        };
+
+        Ok(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RESP_FRAME_TOKEN, ValueRefVisitor)
+    }
+}
+
+/// Binary safe blob, serialized as bytes instead of a seq of u8
+pub(crate) struct Bytes<'a>(pub(crate) &'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Map entries in the order received
+struct Entries<'a, T>(&'a [(T, T)]);
+
+impl<T: Serialize> Serialize for Entries<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+/// Serializes each variant with its own RESP type marker, so a value
+/// deserialized by [`Deserializer`] is written back identically
+/// (attributes excepted, they are not kept)
+macro_rules! impl_serialize_value {
+    ($type_name:ident$(<$lt:lifetime>)?) => {
+        impl$(<$lt>)? Serialize for $type_name$(<$lt>)? {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                match self {
+                    $type_name::SimpleString(s) => {
+                        serializer.serialize_newtype_struct(SIMPLE_STRING_TOKEN, s)
+                    }
+                    $type_name::SimpleError(s) => {
+                        serializer.serialize_newtype_struct(SIMPLE_ERROR_TOKEN, s)
+                    }
+                    $type_name::BlobString(b) => {
+                        serializer.serialize_newtype_struct(BLOB_STRING_TOKEN, &Bytes(&b[..]))
+                    }
+                    $type_name::BlobError(s) => {
+                        serializer.serialize_newtype_struct(BLOB_ERROR_TOKEN, s)
+                    }
+                    $type_name::VerbatimString { format, text } => serializer
+                        .serialize_newtype_struct(
+                            VERBATIM_STRING_TOKEN,
+                            &format!("{}:{}", format, text),
+                        ),
+                    $type_name::Integer(i) => serializer.serialize_i64(*i),
+                    $type_name::Double(d) => serializer.serialize_f64(*d),
+                    $type_name::Boolean(b) => serializer.serialize_bool(*b),
+                    $type_name::BigNumber(n) => {
+                        serializer.serialize_newtype_struct(BIG_NUMBER_TOKEN, n)
+                    }
+                    $type_name::Null => serializer.serialize_unit(),
+                    $type_name::Array(values) => values.serialize(serializer),
+                    $type_name::Set(values) => serializer.serialize_newtype_struct(SET_TOKEN, values),
+                    $type_name::Map(entries) => Entries(entries).serialize(serializer),
+                    $type_name::Push(values) => {
+                        serializer.serialize_newtype_struct(PUSH_TOKEN, values)
+                    }
+                }
+            }
+        }
+    };
+}
+impl_serialize_value!(Value);
+impl_serialize_value!(ValueRef<'de>);
+
+/// Deserializes `T` from an already parsed [`Value`], for two-phase
+/// parsing: read a reply once as [`Value`], pick its concrete type later.
+///
+/// ```rust
+/// use deseresp::{from_slice, from_value, Value};
+///
+/// let value: Value = from_slice(b"%1\r\n+id\r\n:7\r\n").unwrap();
+/// #[derive(serde::Deserialize)]
+/// struct Reply {
+///     id: u64,
+/// }
+/// let reply: Reply = from_value(value).unwrap();
+/// assert_eq!(reply.id, 7);
+/// ```
+pub fn from_value<T: DeserializeOwned>(value: Value) -> crate::Result<T> {
+    T::deserialize(value)
+}
+
+impl Value {
+    /// RESP type, tagging the value as [`crate::Deserializer`] does
+    fn kind(&self) -> Kind {
+        match self {
+            Value::SimpleString(_) => Kind::SimpleString,
+            Value::SimpleError(_) => Kind::SimpleError,
+            Value::BlobString(_) => Kind::BlobString,
+            Value::BlobError(_) => Kind::BlobError,
+            Value::VerbatimString { .. } => Kind::VerbatimString,
+            Value::Integer(_) => Kind::Integer,
+            Value::Double(_) => Kind::Double,
+            Value::Boolean(_) => Kind::Boolean,
+            Value::BigNumber(_) => Kind::BigNumber,
+            Value::Null => Kind::Null,
+            Value::Array(_) => Kind::Array,
+            Value::Set(_) => Kind::Set,
+            Value::Map(_) => Kind::Map,
+            Value::Push(_) => Kind::Push,
+        }
+    }
+
+    /// Error for a value of another RESP type than `expected`, the same
+    /// [`Error::UnexpectedMarker`] the wire would give
+    fn unexpected(&self, expected: &'static str) -> Error {
+        Error::expected_marker_found(expected, self.kind().marker())
+    }
+}
+
+/// Blob strings are visited as strings when valid UTF-8, like
+/// [`Deserializer`] does
+fn visit_blob<'de, V: Visitor<'de>>(bytes: Vec<u8>, visitor: V) -> crate::Result<V::Value> {
+    match String::from_utf8(bytes) {
+        Ok(s) => visitor.visit_string(s),
+        Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::SimpleString(s)
+            | Value::SimpleError(s)
+            | Value::BlobError(s)
+            | Value::BigNumber(s) => visitor.visit_string(s),
+            // visited as the raw `<format>:<text>` of the wire, like from_slice
+            Value::VerbatimString { format, text } => {
+                visitor.visit_string(format!("{}:{}", format, text))
+            }
+            Value::BlobString(b) => visit_blob(b, visitor),
+            Value::Integer(i) => visitor.visit_i64(i),
+            Value::Double(d) => visitor.visit_f64(d),
+            Value::Boolean(b) => visitor.visit_bool(b),
+            Value::Null => visitor.visit_unit(),
+            Value::Array(values) | Value::Set(values) | Value::Push(values) => {
+                visitor.visit_seq(SeqDeserializer::new(values.into_iter()))
+            }
+            Value::Map(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+        }
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::BigNumber(n) => visitor.visit_i128(n.parse().map_err(|_| Error::overflow())?),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::BigNumber(n) => visitor.visit_u128(n.parse().map_err(|_| Error::overflow())?),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::BlobString(b) => visitor.visit_byte_buf(b),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match (name, self) {
+            (RESP_VALUE_TOKEN | RESP_FRAME_TOKEN, value) => visitor.visit_enum(KindAccess(value)),
+            (SIMPLE_STRING_TOKEN, Value::SimpleString(s))
+            | (SIMPLE_ERROR_TOKEN, Value::SimpleError(s))
+            | (BLOB_ERROR_TOKEN, Value::BlobError(s)) => visitor.visit_string(s),
+            (BLOB_STRING_TOKEN, Value::BlobString(b)) => visit_blob(b, visitor),
+            (SIMPLE_STRING_TOKEN, value) => Err(value.unexpected("'+' for SimpleString")),
+            (SIMPLE_ERROR_TOKEN, value) => Err(value.unexpected("'-' for SimpleError")),
+            (BLOB_ERROR_TOKEN, value) => Err(value.unexpected("'!' for BlobError")),
+            (BLOB_STRING_TOKEN, value) => Err(value.unexpected("'$' for BlobString")),
+            (PUSH_TOKEN, value @ Value::Push(_)) => visitor.visit_newtype_struct(value),
+            (PUSH_TOKEN, value) => Err(value.unexpected("'>' for Push")),
+            (SET_TOKEN, value @ Value::Set(_)) => visitor.visit_newtype_struct(value),
+            (SET_TOKEN, value) => Err(value.unexpected("'~' for Set")),
+            (_, value) => visitor.visit_newtype_struct(value),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::SimpleString(s) => visitor.visit_enum(s.into_deserializer()),
+            Value::BlobString(b) => {
+                let s = str_from_utf8(&b)?;
+                visitor.visit_enum(s.into_deserializer())
+            }
+            Value::Map(entries) if entries.len() == 1 => {
+                let map = MapDeserializer::new(entries.into_iter());
+                visitor.visit_enum(MapAccessDeserializer::new(map))
+            }
+            Value::Map(_) => Err(Error::expected_value("1-length map")),
+            // tagged array: variant name followed by the variant's elements
+            Value::Array(values) | Value::Push(values) => {
+                visitor.visit_enum(SeqEnumAccess(values.into_iter()))
+            }
+            value => Err(value.unexpected("'%', '*', '>' or string for enum")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        seq unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// EnumAccess tagging a [`Value`] with its RESP type, see [`Kind`]
+struct KindAccess(Value);
+
+impl<'de> EnumAccess<'de> for KindAccess {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> crate::Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let kind: de::value::StrDeserializer<Error> = self.0.kind().name().into_deserializer();
+        let kind = seed.deserialize(kind)?;
+        Ok((kind, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for KindAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> crate::Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0 {
+            // read back as the raw `<format>:<text>` of the wire
+            Value::VerbatimString { format, text } => {
+                seed.deserialize(Value::SimpleString(format!("{}:{}", format, text)))
+            }
+            value => seed.deserialize(value),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        serde::Deserializer::deserialize_any(self.0, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        serde::Deserializer::deserialize_any(self.0, visitor)
+    }
+}
+
+/// EnumAccess over a tagged array, the variant name comes first
+struct SeqEnumAccess(std::vec::IntoIter<Value>);
+
+impl<'de> EnumAccess<'de> for SeqEnumAccess {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> crate::Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let name = self
+            .0
+            .next()
+            .ok_or_else(|| Error::expected_value("variant name"))?;
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for SeqEnumAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> crate::Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let value = self
+            .0
+            .next()
+            .ok_or_else(|| Error::expected_value("element"))?;
+        seed.deserialize(value)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(self.0))
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> crate::Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(self.0))
+    }
+}
+
+struct AnyScalarVisitor;
+
+impl<'de> Visitor<'de> for AnyScalarVisitor {
+    type Value = AnyScalar;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "expecting scalar RESP value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(i64::try_from(v).map_or(AnyScalar::UInt(v), AnyScalar::Int))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Text(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Text(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(AnyScalar::Null)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    /// Tagged value from [`crate::Deserializer`], variant is the RESP type
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (kind, variant) = data.variant()?;
+        let value = match kind {
+            Kind::SimpleString | Kind::SimpleError | Kind::BlobError => {
+                AnyScalar::Text(variant.newtype_variant()?)
+            }
+            Kind::BlobString => {
+                let bytes = variant.newtype_variant::<ByteBuf>()?.0;
+                match String::from_utf8(bytes) {
+                    Ok(s) => AnyScalar::Text(s),
+                    Err(e) => {
+                        return Err(de::Error::invalid_value(
+                            de::Unexpected::Bytes(e.as_bytes()),
+                            &"utf-8 text",
+                        ))
+                    }
+                }
+            }
+            Kind::VerbatimString => {
+                let s: String = variant.newtype_variant()?;
+                AnyScalar::Text(split_verbatim(&s)?.1.to_owned())
+            }
+            Kind::Integer => AnyScalar::Int(variant.newtype_variant()?),
+            Kind::Double => AnyScalar::Double(variant.newtype_variant()?),
+            Kind::Boolean => AnyScalar::Bool(variant.newtype_variant()?),
+            Kind::BigNumber => {
+                let s: String = variant.newtype_variant()?;
+                if let Ok(v) = s.parse() {
+                    AnyScalar::Int(v)
+                } else if let Ok(v) = s.parse() {
+                    AnyScalar::UInt(v)
+                } else {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Str(&s),
+                        &"big number in range of u64",
+                    ));
+                }
+            }
+            Kind::Null => {
+                variant.unit_variant()?;
+                AnyScalar::Null
+            }
+            Kind::Array | Kind::Set | Kind::Push => {
+                return Err(de::Error::invalid_type(de::Unexpected::Seq, &self))
+            }
+            Kind::Map => return Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyScalar {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RESP_VALUE_TOKEN, AnyScalarVisitor)
+    }
+}
+
+/// Renders every RESP value in the input like `redis-cli` does, one
+/// value per line, nested aggregates indented under their index.
+///
+/// ```rust
+/// let s = deseresp::to_debug_string(b"*2\r\n:1\r\n*1\r\n$2\r\nab\r\n").unwrap();
+/// assert_eq!(s, "1) (integer) 1\n2) 1) \"ab\"");
+/// ```
+pub fn to_debug_string(bytes: &[u8]) -> crate::Result<String> {
+    let mut de = Deserializer::from_slice(bytes);
+    let mut out = String::new();
+    while de.get_consumed_bytes() < bytes.len() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        match de.next_frame()? {
+            Frame::Push(value) | Frame::Reply(value) => value.render(&mut out, 0),
+        }
+    }
+
+    Ok(out)
+}
+
+impl Value {
+    /// Renders to `out`, continuation lines are indented by `indent` columns
+    fn render(&self, out: &mut String, indent: usize) {
+        match self {
+            Value::SimpleString(s) => out.push_str(s),
+            Value::SimpleError(s) | Value::BlobError(s) => {
+                out.push_str("(error) ");
+                out.push_str(s);
+            }
+            Value::BlobString(b) => push_quoted(out, b),
+            Value::VerbatimString { format, text } => {
+                out.push_str(&format!("(verbatim {}) ", format));
+                push_quoted(out, text.as_bytes());
+            }
+            Value::Integer(i) => out.push_str(&format!("(integer) {}", i)),
+            Value::Double(d) => out.push_str(&format!("(double) {}", d)),
+            Value::Boolean(b) => out.push_str(&format!("({})", b)),
+            Value::BigNumber(n) => out.push_str(&format!("(big number) {}", n)),
+            Value::Null => out.push_str("(nil)"),
+            Value::Array(values) => render_seq(out, indent, values, ")", "array"),
+            Value::Set(values) => render_seq(out, indent, values, "~", "set"),
+            Value::Push(values) => render_seq(out, indent, values, ">", "push"),
+            Value::Map(entries) => {
+                if entries.is_empty() {
+                    out.push_str("(empty map)");
+                }
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    let line_start = start_item(out, indent, i, "#");
+                    let key_indent = out.len() - line_start;
+                    k.render(out, key_indent);
+                    out.push_str(" => ");
+                    let line_start = out.rfind('\n').map_or(0, |n| n + 1);
+                    let value_indent = out.len() - line_start;
+                    v.render(out, value_indent);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.render(&mut out, 0);
+        f.write_str(&out)
+    }
+}
+
+fn render_seq(out: &mut String, indent: usize, values: &[Value], suffix: &str, name: &str) {
+    if values.is_empty() {
+        out.push_str(&format!("(empty {})", name));
+    }
+    for (i, v) in values.iter().enumerate() {
+        let line_start = start_item(out, indent, i, suffix);
+        let item_indent = out.len() - line_start;
+        v.render(out, item_indent);
+    }
+}
+
+/// Writes the `1) ` index of the item on a new line if not the first one,
+/// returns where the line starts in `out`
+fn start_item(out: &mut String, indent: usize, i: usize, suffix: &str) -> usize {
+    if i > 0 {
+        out.push('\n');
+        out.extend(std::iter::repeat_n(' ', indent));
+    }
+    let line_start = out.rfind('\n').map_or(0, |n| n + 1);
+    out.push_str(&format!("{}{} ", i + 1, suffix));
+    line_start
+}
+
+fn push_quoted(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+    for &b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        test_utils::{test_deserialize, test_deserialize_result},
+        Error,
+    };
+
+    #[test]
+    fn test_deserialize_value() {
+        test_deserialize(b"+OK\r\n", |value: Value| {
+            assert_eq!(value, Value::SimpleString("OK".into()));
+        });
+        test_deserialize(b"-ERR bad\r\n", |value: Value| {
+            assert_eq!(value, Value::SimpleError("ERR bad".into()));
+        });
+        test_deserialize(b"$3\r\n\x00\xff\x01\r\n", |value: Value| {
+            assert_eq!(value, Value::BlobString(vec![0, 0xff, 1]));
+        });
+        test_deserialize(b"=15\r\ntxt:Some string\r\n", |value: Value| {
+            assert_eq!(
+                value,
+                Value::VerbatimString {
+                    format: "txt".into(),
+                    text: "Some string".into()
+                }
+            );
+        });
+        test_deserialize(
+            b"(3492890328409238509324850943850943825024385\r\n",
+            |value: Value| {
+                assert_eq!(
+                    value,
+                    Value::BigNumber("3492890328409238509324850943850943825024385".into())
+                );
+            },
+        );
+        test_deserialize(
+            b"*3\r\n:1\r\n~2\r\n#t\r\n_\r\n%1\r\n+a\r\n,1.5\r\n",
+            |value: Value| {
+                assert_eq!(
+                    value,
+                    Value::Array(vec![
+                        Value::Integer(1),
+                        Value::Set(vec![Value::Boolean(true), Value::Null]),
+                        Value::Map(vec![(Value::SimpleString("a".into()), Value::Double(1.5))]),
+                    ])
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_deserialize_value_ref() {
+        let input =
+            b"*4\r\n+OK\r\n$3\r\n\x00\xff\x01\r\n=8\r\ntxt:some\r\n%1\r\n(12\r\n~1\r\n_\r\n";
+        let value: ValueRef = crate::from_slice(input).unwrap();
+        assert_eq!(
+            value,
+            ValueRef::Array(vec![
+                ValueRef::SimpleString("OK"),
+                ValueRef::BlobString(b"\x00\xff\x01"),
+                ValueRef::VerbatimString {
+                    format: "txt",
+                    text: "some"
+                },
+                ValueRef::Map(vec![(
+                    ValueRef::BigNumber("12"),
+                    ValueRef::Set(vec![ValueRef::Null])
+                )]),
+            ])
+        );
+
+        // strings point into the input
+        let ValueRef::Array(values) = value else {
+            unreachable!()
+        };
+        let (ValueRef::SimpleString(ok), ValueRef::BlobString(blob)) = (&values[0], &values[1])
+        else {
+            unreachable!()
+        };
+        assert_eq!(ok.as_ptr(), input[5..].as_ptr());
+        assert_eq!(blob.as_ptr(), input[13..].as_ptr());
+
+        // nothing to borrow from a reader
+        let mut d = Deserializer::from_read(&input[..]);
+        assert!(ValueRef::deserialize(&mut d).is_err());
+    }
+
+    #[test]
+    fn test_serialize_value() {
+        let input: &[u8] = b"*5\r\n+OK\r\n-ERR bad\r\n$3\r\n\x00\xff\r\r\n!4\r\nE\r\nR\r\n\
+            %2\r\n=8\r\ntxt:some\r\n~3\r\n:-1\r\n,1.5\r\n#t\r\n\
+            (3492890328409238509324850943850943825024385\r\n*2\r\n_\r\n*0\r\n";
+        let value: Value = crate::from_slice(input).unwrap();
+        assert_eq!(crate::to_vec(&value).unwrap(), input);
+        let value: ValueRef = crate::from_slice(input).unwrap();
+        assert_eq!(crate::to_vec(&value).unwrap(), input);
+
+        let input = b">2\r\n+invalidate\r\n*1\r\n$3\r\nkey\r\n";
+        let Frame::Push(value) = Deserializer::from_slice(input).next_frame().unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(crate::to_vec(&value).unwrap(), input);
+
+        // rewrite a field then re-emit
+        let mut value: Value = crate::from_slice(b"%1\r\n+name\r\n$5\r\nalice\r\n").unwrap();
+        if let Value::Map(entries) = &mut value {
+            entries[0].1 = Value::BlobString(b"bob".to_vec());
+        }
+        assert_eq!(
+            crate::to_vec(&value).unwrap(),
+            b"%1\r\n+name\r\n$3\r\nbob\r\n"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_any_scalar() {
+        test_deserialize(b"+OK\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Text("OK".into()));
+        });
+        test_deserialize(b"-ERR bad\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Text("ERR bad".into()));
+        });
+        test_deserialize(b"$5\r\nhello\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Text("hello".into()));
+        });
+        test_deserialize(b"!3\r\nERR\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Text("ERR".into()));
+        });
+        test_deserialize(b"=8\r\ntxt:some\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Text("some".into()));
+        });
+        test_deserialize(b":-42\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Int(-42));
+        });
+        test_deserialize(b",1.5\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Double(1.5));
+        });
+        test_deserialize(b"#f\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Bool(false));
+        });
+        test_deserialize(b"(123\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Int(123));
+        });
+        test_deserialize(b"(18446744073709551615\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::UInt(u64::MAX));
+        });
+        test_deserialize(b"_\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Null);
+        });
+        test_deserialize(b"|1\r\n+ttl\r\n:1\r\n:7\r\n", |value: AnyScalar| {
+            assert_eq!(value, AnyScalar::Int(7));
+        });
+    }
+
+    #[test]
+    fn test_deserialize_set() {
+        let input = b"~2\r\n:1\r\n:2\r\n";
+        test_deserialize(input, |value: Vec<i64>| {
+            assert_eq!(value, [1, 2]);
+        });
+        test_deserialize(input, |value: Value| {
+            assert_eq!(
+                value,
+                Value::Set(vec![Value::Integer(1), Value::Integer(2)])
+            );
+        });
+        test_deserialize(input, |value: ValueRef| {
+            assert_eq!(
+                value,
+                ValueRef::Set(vec![ValueRef::Integer(1), ValueRef::Integer(2)])
+            );
+        });
+        test_deserialize(
+            b"%1\r\n+a\r\n~1\r\n_\r\n",
+            |value: HashMap<String, Value>| {
+                assert_eq!(value["a"], Value::Set(vec![Value::Null]));
+            },
+        );
+
+        // buffered by serde, the set marker is lost
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Reply {
+            Values(Value),
+        }
+        test_deserialize(input, |value: Reply| {
+            let Reply::Values(value) = value;
+            assert_eq!(
+                value,
+                Value::Array(vec![Value::Integer(1), Value::Integer(2)])
+            );
+        });
+    }
+
+    #[test]
+    fn test_from_value() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Reply {
+            id: u64,
+            name: String,
+            score: f64,
+            tags: Vec<String>,
+            parent: Option<u64>,
+            kind: Kind,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Kind {
+            User,
+            Bot(u64),
+        }
+
+        let input = b"%6\r\n+id\r\n:7\r\n$4\r\nname\r\n$5\r\nalice\r\n+score\r\n,1.5\r\n\
+            +tags\r\n~2\r\n+a\r\n+b\r\n+parent\r\n_\r\n+kind\r\n%1\r\n+Bot\r\n:3\r\n";
+        let value: Value = crate::from_slice(input).unwrap();
+        assert!(matches!(value, Value::Map(_)));
+        let reply: Reply = from_value(value).unwrap();
+        assert_eq!(
+            reply,
+            Reply {
+                id: 7,
+                name: "alice".into(),
+                score: 1.5,
+                tags: vec!["a".into(), "b".into()],
+                parent: None,
+                kind: Kind::Bot(3),
+            }
+        );
+
+        let value = Value::Array(vec![Value::SimpleString("Bot".into()), Value::Integer(1)]);
+        assert_eq!(from_value::<Kind>(value).unwrap(), Kind::Bot(1));
+        let value = Value::BlobString(b"User".to_vec());
+        assert_eq!(
+            Kind::deserialize(value.into_deserializer()).unwrap(),
+            Kind::User
+        );
+
+        // the RESP types are kept when reading back a Value
+        let input = b"*4\r\n$2\r\n\xff\x00\r\n=7\r\ntxt:abc\r\n(12\r\n%1\r\n-ERR\r\n~0\r\n";
+        let value: Value = crate::from_slice(input).unwrap();
+        assert_eq!(from_value::<Value>(value.clone()).unwrap(), value);
+        let value = Value::Push(vec![Value::BlobError("ERR".into()), Value::Null]);
+        assert_eq!(from_value::<Value>(value.clone()).unwrap(), value);
+
+        let value = Value::BlobString(b"\xff\x00".to_vec());
+        let bytes = from_value::<serde_bytes::ByteBuf>(value).unwrap();
+        assert_eq!(bytes.as_slice(), b"\xff\x00");
+        // blobs aren't sequences, same as on the wire
+        assert!(from_value::<Vec<u8>>(Value::BlobString(b"ab".to_vec())).is_err());
+        assert!(from_value::<Vec<u64>>(Value::BlobString(b"ab".to_vec())).is_err());
+
+        // verbatim strings read as String the same from a Value and from the wire
+        let input = b"=8\r\ntxt:some\r\n";
+        let value: Value = crate::from_slice(input).unwrap();
+        let text: String = crate::from_slice(input).unwrap();
+        assert_eq!(text, "txt:some");
+        assert_eq!(from_value::<String>(value).unwrap(), text);
+        let value = Value::BigNumber("123456789012345678901234567890".into());
+        assert_eq!(
+            from_value::<u128>(value).unwrap(),
+            123456789012345678901234567890
+        );
+
+        let value = Value::BlobString(b"OK".to_vec());
+        let reply = from_value::<crate::types::owned::SimpleString>(value);
+        let err = Error::expected_marker_found("'+' for SimpleString", markers::BLOB_STRING);
+        assert_eq!(reply.unwrap_err(), err);
+    }
+
+    #[test]
+    fn test_deserialize_any_scalar_aggregate() {
+        let inputs: [&[u8]; 4] = [
+            b"*1\r\n:1\r\n",
+            b"~1\r\n:1\r\n",
+            b"%1\r\n+a\r\n:1\r\n",
+            b"(123456789012345678901234567890\r\n",
+        ];
+        for input in inputs {
+            test_deserialize_result(input, |value: Result<AnyScalar, Error>| {
+                assert!(matches!(value, Err(Error::Custom(_))), "{:?}", value);
+            });
+        }
+    }
+
+    #[test]
+    fn test_next_frame() {
+        let input = b">3\r\n+message\r\n+channel\r\n+hello\r\n:42\r\n";
+        let mut d = Deserializer::from_slice(input);
+        let frame = d.next_frame().unwrap();
+        assert_eq!(
+            frame,
+            Frame::Push(Value::Push(vec![
+                Value::SimpleString("message".into()),
+                Value::SimpleString("channel".into()),
+                Value::SimpleString("hello".into()),
+            ]))
+        );
+        let frame = d.next_frame().unwrap();
+        assert_eq!(frame, Frame::Reply(Value::Integer(42)));
+
+        let mut d = Deserializer::from_read(&input[..]);
+        assert!(matches!(d.next_frame().unwrap(), Frame::Push(_)));
+        assert!(matches!(d.next_frame().unwrap(), Frame::Reply(_)));
+    }
+
+    #[test]
+    fn test_to_debug_string() {
+        let s = to_debug_string(b"+OK\r\n-ERR bad\r\n$4\r\na\"\n\x00\r\n_\r\n").unwrap();
+        assert_eq!(s, "OK\n(error) ERR bad\n\"a\\\"\\n\\x00\"\n(nil)");
+
+        let input = b"*3\r\n*2\r\n:1\r\n,1.5\r\n%2\r\n+a\r\n~1\r\n#t\r\n+b\r\n*0\r\n(12\r\n";
+        let s = to_debug_string(input).unwrap();
+        assert_eq!(
+            s,
+            "1) 1) (integer) 1\n   2) (double) 1.5\n2) 1# a => 1~ (true)\n   2# b => (empty array)\n3) (big number) 12"
+        );
+
+        let s = to_debug_string(b"=8\r\nmkd:*hi*\r\n").unwrap();
+        assert_eq!(s, "(verbatim mkd) \"*hi*\"");
+
+        // pushes are rendered wherever they are
+        let s = to_debug_string(b">2\r\n+message\r\n+hi\r\n+OK\r\n>1\r\n:1\r\n").unwrap();
+        assert_eq!(s, "1> message\n2> hi\nOK\n1> (integer) 1");
+
+        assert!(to_debug_string(b"*2\r\n:1\r\n").is_err());
+    }
+
+    #[test]
+    fn test_value_push() {
+        let input = b">2\r\n+message\r\n+hi\r\n:5\r\n";
+        let push = Value::Push(vec![
+            Value::SimpleString("message".into()),
+            Value::SimpleString("hi".into()),
+        ]);
+        let value: Value = crate::from_slice(input).unwrap();
+        assert_eq!(value, push);
+        let value: ValueRef = crate::from_slice(input).unwrap();
+        assert!(matches!(value, ValueRef::Push(_)));
+
+        // other types still skip the push before the reply
+        let value: u64 = crate::from_slice(input).unwrap();
+        assert_eq!(value, 5);
+        let mut de = Deserializer::from_slice(input);
+        let value = Value::deserialize(&mut de).unwrap();
+        assert_eq!(value, push);
+        assert_eq!(u64::deserialize(&mut de).unwrap(), 5);
+    }
+}
@@ -0,0 +1,195 @@
+//! Replies in the framing redis-server uses on a RESP3 connection
+//!
+//! The fixtures in `fixtures/` are hand-written after the RESP3 spec and
+//! the replies documented for redis 7.2, they aren't recorded from a
+//! server yet. `examples/record_fixtures.rs` records them from a live
+//! server and lists the commands behind each one, once recorded this doc
+//! should name the server version. The assertions leave out what varies
+//! between servers and runs (version, client id) so recorded fixtures
+//! pass as is. `attribute.resp` stays hand-written, no command replies
+//! with an attribute of this shape.
+
+use std::collections::HashMap;
+
+use deseresp::{
+    from_slice, from_slice_result,
+    types::{Push, WithAttribute},
+    Deserializer, Frame, Value,
+};
+use serde::Deserialize;
+
+#[test]
+fn hello() {
+    #[derive(Deserialize, Debug)]
+    struct Hello<'a> {
+        server: &'a str,
+        version: &'a str,
+        proto: u8,
+        id: u64,
+        mode: &'a str,
+        role: &'a str,
+        modules: Vec<Value>,
+    }
+
+    let hello: Hello = from_slice(include_bytes!("fixtures/hello.resp")).unwrap();
+    assert_eq!(hello.server, "redis");
+    assert!(hello.version.split('.').all(|v| v.parse::<u32>().is_ok()));
+    assert_eq!(hello.proto, 3);
+    assert!(hello.id > 0);
+    assert_eq!(hello.mode, "standalone");
+    assert_eq!(hello.role, "master");
+    assert!(hello.modules.is_empty());
+}
+
+#[test]
+fn get() {
+    let value: Option<String> = from_slice(include_bytes!("fixtures/get.resp")).unwrap();
+    assert_eq!(value.as_deref(), Some("hello"));
+
+    let value: Option<String> = from_slice(include_bytes!("fixtures/get_nil.resp")).unwrap();
+    assert_eq!(value, None);
+}
+
+#[test]
+fn hgetall() {
+    #[derive(Deserialize, Debug)]
+    struct Repo {
+        name: String,
+        // hash values are always strings
+        stars: String,
+    }
+
+    let input = include_bytes!("fixtures/hgetall.resp");
+    let repo: Repo = from_slice(input).unwrap();
+    assert_eq!(repo.name, "deseresp");
+    assert_eq!(repo.stars.parse::<u64>().unwrap(), 42);
+
+    let map: HashMap<&str, &str> = from_slice(input).unwrap();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["lang"], "rust");
+}
+
+#[test]
+fn xrange() {
+    let entries: Vec<(String, Vec<String>)> =
+        from_slice(include_bytes!("fixtures/xrange.resp")).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].0, "1526985054069-0");
+    assert_eq!(entries[0].1, ["temperature", "36", "humidity", "95"]);
+    assert_eq!(entries[1].0, "1526985054079-0");
+    assert_eq!(entries[1].1, ["temperature", "37", "humidity", "94"]);
+}
+
+#[test]
+fn client_info() {
+    let value: Value = from_slice(include_bytes!("fixtures/client_info.resp")).unwrap();
+    let Value::VerbatimString { format, text } = value else {
+        panic!("expected verbatim string, got {:?}", value);
+    };
+    assert_eq!(format, "txt");
+    let fields: HashMap<&str, &str> = text
+        .split_ascii_whitespace()
+        .filter_map(|kv| kv.split_once('='))
+        .collect();
+    assert!(fields["id"].parse::<u64>().is_ok());
+    assert_eq!(fields["cmd"], "client|info");
+    assert_eq!(fields["resp"], "3");
+}
+
+#[test]
+fn error_replies() {
+    let reply = from_slice_result::<_, String>(include_bytes!("fixtures/error_wrongtype.resp"))
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(
+        reply.0,
+        "WRONGTYPE Operation against a key holding the wrong kind of value"
+    );
+
+    let reply =
+        from_slice_result::<_, Vec<String>>(include_bytes!("fixtures/error_unknown_command.resp"))
+            .unwrap()
+            .unwrap_err();
+    assert!(reply.0.starts_with("ERR unknown command 'FOO'"));
+}
+
+#[test]
+fn attribute() {
+    #[derive(Deserialize, Debug)]
+    struct KeyPopularity {
+        #[serde(rename = "key-popularity")]
+        key_popularity: HashMap<String, f64>,
+    }
+
+    let input = include_bytes!("fixtures/attribute.resp");
+    let value: (u64, u64) = from_slice(input).unwrap();
+    assert_eq!(value, (2039123, 9543892));
+
+    let value: WithAttribute<KeyPopularity, (u64, u64)> = from_slice(input).unwrap();
+    let (attr, value) = value.into_inner();
+    assert_eq!(value, (2039123, 9543892));
+    assert_eq!(attr.key_popularity["a"], 0.1923);
+    assert_eq!(attr.key_popularity["b"], 0.0012);
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum PushEvent {
+    Subscribe(String, u64),
+    Message { channel: String, payload: String },
+    Invalidate(Option<Vec<String>>),
+}
+
+#[test]
+fn pubsub_push() {
+    let input = include_bytes!("fixtures/subscribe.resp");
+    let mut d = Deserializer::from_slice(input);
+    let event: Push<PushEvent> = Deserialize::deserialize(&mut d).unwrap();
+    assert_eq!(event.0, PushEvent::Subscribe("news".into(), 1));
+    let event: Push<PushEvent> = Deserialize::deserialize(&mut d).unwrap();
+    assert_eq!(
+        event.0,
+        PushEvent::Message {
+            channel: "news".into(),
+            payload: "hello".into()
+        }
+    );
+    d.expect_eof().unwrap();
+}
+
+#[test]
+fn tracking_push_between_replies() {
+    let input = include_bytes!("fixtures/tracking.resp");
+
+    let mut d = Deserializer::from_slice(input);
+    assert_eq!(
+        d.next_frame().unwrap(),
+        Frame::Reply(Value::SimpleString("OK".into()))
+    );
+    let Frame::Push(push) = d.next_frame().unwrap() else {
+        panic!("expected push frame");
+    };
+    assert_eq!(
+        push,
+        Value::Push(vec![
+            Value::BlobString(b"invalidate".to_vec()),
+            Value::Array(vec![Value::BlobString(b"user:1".to_vec())]),
+        ])
+    );
+    assert_eq!(
+        d.next_frame().unwrap(),
+        Frame::Reply(Value::BlobString(b"alice".to_vec()))
+    );
+
+    // pushes are skipped when reading replies only
+    let mut d = Deserializer::from_slice(input);
+    let ok: String = Deserialize::deserialize(&mut d).unwrap();
+    assert_eq!(ok, "OK");
+    let get: String = Deserialize::deserialize(&mut d).unwrap();
+    assert_eq!(get, "alice");
+
+    let mut d = Deserializer::from_slice(input);
+    let _ok: String = Deserialize::deserialize(&mut d).unwrap();
+    let event: Push<PushEvent> = Deserialize::deserialize(&mut d).unwrap();
+    assert_eq!(event.0, PushEvent::Invalidate(Some(vec!["user:1".into()])));
+}